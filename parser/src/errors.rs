@@ -1,15 +1,268 @@
+use std::error::Error as StdError;
 use std::fmt;
+use std::path::PathBuf;
 
-// TODO: proper error handling/reporting
+use value::DateError;
+
+
+/// Where an error happened in a SCL document.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Location {
+    /// The file being parsed, `None` when parsing a string
+    pub path: Option<PathBuf>,
+    /// Byte offsets of the start and end of the offending input
+    pub span: (usize, usize),
+    /// 1-based line of the start of the span
+    pub line: usize,
+    /// 1-based column of the start of the span
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref p) = self.path {
+            write!(f, "{}:", p.display())?;
+        }
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// All the errors that can happen while loading a SCL document.
 #[derive(PartialEq, Debug, Clone)]
 pub enum Error {
-    InvalidSyntax(String),
+    /// The input is not valid SCL
+    InvalidSyntax { message: String, location: Location },
+    /// The file given to `parse_file` could not be read
+    Io { path: PathBuf, message: String },
+    /// A file referenced by an `include` could not be read
+    Include { path: PathBuf, message: String, location: Location },
+    /// An environment variable could not be read
+    EnvVar { name: String, message: String, location: Location },
+    /// A reference to another value of the document could not be resolved
+    Reference { path: String, message: String, location: Location },
+    /// A value doesn't have the type required by its context, `origin` being what
+    /// requires that type if it's in the document: an env var cast or the first item of an array
+    TypeMismatch {
+        expected: &'static str,
+        found: &'static str,
+        location: Location,
+        origin: Option<Box<Location>>,
+    },
+    /// The content of an environment variable could not be cast to the requested type
+    InvalidCast { value: String, cast: String, location: Location },
+    /// A value is well-formed but doesn't make sense, like the 31st of April
+    InvalidValue { message: String, location: Location },
+    /// A number doesn't fit in the type it is stored as
+    OutOfRange { value: String, type_name: &'static str, location: Location },
+    /// A key is set several times in the same dictionary.
+    /// This is only reported as a warning by `check_file`/`check_str`.
+    DuplicateKey { key: String, location: Location, previous: Box<Location> },
+    /// An error happened in the file included at `location`
+    Included { location: Location, cause: Box<Error> },
+}
+
+impl Error {
+    /// Where the error happened, if it can be pinpointed in a document.
+    /// For errors in included files, this is the location in the included file:
+    /// see `include_chain` to know how we got there.
+    pub fn location(&self) -> Option<&Location> {
+        match *self {
+            Error::Io { .. } => None,
+            Error::Included { ref cause, .. } => cause.location(),
+            Error::InvalidSyntax { ref location, .. }
+            | Error::Include { ref location, .. }
+            | Error::EnvVar { ref location, .. }
+            | Error::Reference { ref location, .. }
+            | Error::TypeMismatch { ref location, .. }
+            | Error::InvalidCast { ref location, .. }
+            | Error::InvalidValue { ref location, .. }
+            | Error::OutOfRange { ref location, .. }
+            | Error::DuplicateKey { ref location, .. } => Some(location),
+        }
+    }
+
+    /// The error itself, without the include statements leading to it.
+    pub fn root_cause(&self) -> &Error {
+        match *self {
+            Error::Included { ref cause, .. } => cause.root_cause(),
+            _ => self,
+        }
+    }
+
+    /// The location of the `include` statements that led to the file with the error,
+    /// starting from the innermost one.
+    /// It is empty if the error happened in the file or string being parsed.
+    pub fn include_chain(&self) -> Vec<&Location> {
+        match *self {
+            Error::Included { ref location, ref cause } => {
+                let mut chain = cause.include_chain();
+                chain.push(location);
+                chain
+            }
+            _ => vec![],
+        }
+    }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::InvalidSyntax(ref s) => write!(f, "{}", s),
+            Error::InvalidSyntax { ref message, ref location } => {
+                write!(f, "{}: {}", location, message)
+            }
+            Error::Io { ref path, ref message } => {
+                write!(f, "failed to read {}: {}", path.display(), message)
+            }
+            Error::Include { ref path, ref message, ref location } => {
+                write!(f, "{}: failed to include {}: {}", location, path.display(), message)
+            }
+            Error::EnvVar { ref name, ref message, ref location } => {
+                write!(f, "{}: environment variable `{}` {}", location, name, message)
+            }
+            Error::Reference { ref path, ref message, ref location } => {
+                write!(f, "{}: reference `&{}` {}", location, path, message)
+            }
+            Error::TypeMismatch { expected, found, ref location, .. } => {
+                write!(f, "{}: expected a value of type {}, found {}", location, expected, found)
+            }
+            Error::InvalidCast { ref value, ref cast, ref location } => {
+                write!(f, "{}: cannot cast `{}` to {}", location, value, cast)
+            }
+            Error::InvalidValue { ref message, ref location } => {
+                write!(f, "{}: {}", location, message)
+            }
+            Error::OutOfRange { ref value, type_name, ref location } => {
+                write!(f, "{}: `{}` is out of range for {}", location, value, type_name)
+            }
+            Error::DuplicateKey { ref key, ref location, .. } => {
+                write!(f, "{}: `{}` is already set in this dictionary and will be overwritten", location, key)
+            }
+            Error::Included { ref location, ref cause } => {
+                write!(f, "{}\n  included from {}", cause, location)
+            }
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            Error::Included { ref cause, .. } => Some(&**cause),
+            _ => None,
+        }
+    }
+}
+
+/// How serious a diagnostic is.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Severity {
+    /// The document can't be loaded
+    Error,
+    /// The document can be loaded but probably doesn't do what the author expects
+    Warning,
+}
+
+/// A problem found by `check_file`/`check_str`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub error: Error,
+}
+
+impl Diagnostic {
+    /// Applies `f` to the error of that diagnostic, keeping the severity
+    pub(crate) fn map<F: FnOnce(Error) -> Error>(self, f: F) -> Diagnostic {
+        Diagnostic { severity: self.severity, error: f(self.error) }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.severity {
+            Severity::Error => write!(f, "error: {}", self.error),
+            Severity::Warning => write!(f, "warning: {}", self.error),
         }
     }
 }
+
+/// The error returned when converting a `Value` to a Rust type that doesn't match its type.
+#[derive(PartialEq, Debug, Clone)]
+pub struct TypeError {
+    /// The type required by the conversion, as returned by `Value::type_str`
+    pub expected: &'static str,
+    /// The type of the value, as returned by `Value::type_str`
+    pub found: &'static str,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "expected a value of type {}, found {}", self.expected, self.found)
+    }
+}
+
+impl StdError for TypeError {}
+
+/// The error returned when converting a `Value` to the date or time type of another crate:
+/// the value can be of another type, or be a date or time built by hand that doesn't exist.
+#[derive(PartialEq, Debug, Clone)]
+pub enum ConversionError {
+    Type(TypeError),
+    Date(DateError),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConversionError::Type(ref e) => e.fmt(f),
+            ConversionError::Date(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl StdError for ConversionError {}
+
+impl From<TypeError> for ConversionError {
+    fn from(e: TypeError) -> ConversionError {
+        ConversionError::Type(e)
+    }
+}
+
+impl From<DateError> for ConversionError {
+    fn from(e: DateError) -> ConversionError {
+        ConversionError::Date(e)
+    }
+}
+
+/// The error returned when a path like `servers.alpha.ip` can't be used.
+#[derive(PartialEq, Debug, Clone)]
+pub struct PathError {
+    /// The path that was given
+    pub path: String,
+    /// What is wrong with it
+    pub message: String,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid path `{}`: {}", self.path, self.message)
+    }
+}
+
+impl StdError for PathError {}
+
+/// The error returned when a value can't be written as SCL.
+#[derive(PartialEq, Debug, Clone)]
+pub struct SerializeError {
+    /// The path of the value, like `servers.alpha.ip`
+    pub path: String,
+    /// Why it can't be written
+    pub message: String,
+}
+
+impl fmt::Display for SerializeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot write `{}`: {}", self.path, self.message)
+    }
+}
+
+impl StdError for SerializeError {}
@@ -0,0 +1,175 @@
+//! Conversions between our dates and times and the ones of the popular date crates,
+//! each behind a feature of the same name as the crate.
+//!
+//! Converting one of our values fails if it was built by hand without its `new`
+//! and doesn't exist: the parser only gives valid ones.
+#[cfg(feature = "chrono")]
+use chrono::{self, Datelike};
+#[cfg(feature = "time")]
+use time;
+use std::convert::TryFrom;
+use std::fmt;
+
+use errors::ConversionError;
+use value::{Date, DateError, DateTime, Time, Value};
+
+
+/// For the checked values the other crates would still reject
+fn unsupported<T: fmt::Display>(value: &T) -> DateError {
+    DateError(format!("{} can't be represented", value))
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<Date> for chrono::NaiveDate {
+    type Error = DateError;
+
+    fn try_from(date: Date) -> Result<chrono::NaiveDate, DateError> {
+        let date = date.checked()?;
+        chrono::NaiveDate::from_ymd_opt(i32::from(date.year), u32::from(date.month), u32::from(date.day))
+            .ok_or_else(|| unsupported(&date))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::NaiveDate> for Date {
+    type Error = DateError;
+
+    fn try_from(date: chrono::NaiveDate) -> Result<Date, DateError> {
+        let year = u16::try_from(date.year())
+            .map_err(|_| DateError(format!("year {} is not between 1000 and 9999", date.year())))?;
+        Date::new(year, date.month() as u8, date.day() as u8)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<Value> for chrono::NaiveDate {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<chrono::NaiveDate, ConversionError> {
+        Ok(chrono::NaiveDate::try_from(Date::try_from(value)?)?)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<Time> for chrono::NaiveTime {
+    type Error = DateError;
+
+    fn try_from(t: Time) -> Result<chrono::NaiveTime, DateError> {
+        let t = t.checked()?;
+        chrono::NaiveTime::from_hms_nano_opt(u32::from(t.hour), u32::from(t.minute), u32::from(t.second), t.nanosecond)
+            .ok_or_else(|| unsupported(&t))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<DateTime> for chrono::DateTime<chrono::FixedOffset> {
+    type Error = DateError;
+
+    fn try_from(datetime: DateTime) -> Result<chrono::DateTime<chrono::FixedOffset>, DateError> {
+        let datetime = datetime.checked()?;
+        let offset = chrono::FixedOffset::east_opt(i32::from(datetime.offset) * 60).ok_or_else(|| unsupported(&datetime))?;
+        let local = chrono::NaiveDateTime::new(
+            chrono::NaiveDate::try_from(datetime.date.clone())?,
+            chrono::NaiveTime::try_from(datetime.time.clone())?,
+        );
+        local
+            .checked_sub_offset(offset)
+            .map(|utc| chrono::DateTime::from_naive_utc_and_offset(utc, offset))
+            .ok_or_else(|| unsupported(&datetime))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<Value> for chrono::NaiveTime {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<chrono::NaiveTime, ConversionError> {
+        Ok(chrono::NaiveTime::try_from(Time::try_from(value)?)?)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<Value> for chrono::DateTime<chrono::FixedOffset> {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<chrono::DateTime<chrono::FixedOffset>, ConversionError> {
+        Ok(chrono::DateTime::try_from(DateTime::try_from(value)?)?)
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<Date> for time::Date {
+    type Error = DateError;
+
+    fn try_from(date: Date) -> Result<time::Date, DateError> {
+        let date = date.checked()?;
+        time::Month::try_from(date.month)
+            .and_then(|month| time::Date::from_calendar_date(i32::from(date.year), month, date.day))
+            .map_err(|_| unsupported(&date))
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<time::Date> for Date {
+    type Error = DateError;
+
+    fn try_from(date: time::Date) -> Result<Date, DateError> {
+        let year = u16::try_from(date.year())
+            .map_err(|_| DateError(format!("year {} is not between 1000 and 9999", date.year())))?;
+        Date::new(year, date.month() as u8, date.day())
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<Value> for time::Date {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<time::Date, ConversionError> {
+        Ok(time::Date::try_from(Date::try_from(value)?)?)
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<Time> for time::Time {
+    type Error = DateError;
+
+    fn try_from(t: Time) -> Result<time::Time, DateError> {
+        let t = t.checked()?;
+        time::Time::from_hms_nano(t.hour, t.minute, t.second, t.nanosecond).map_err(|_| unsupported(&t))
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<DateTime> for time::OffsetDateTime {
+    type Error = DateError;
+
+    fn try_from(datetime: DateTime) -> Result<time::OffsetDateTime, DateError> {
+        let datetime = datetime.checked()?;
+        let minutes = datetime.offset;
+        let offset = time::UtcOffset::from_hms((minutes / 60) as i8, (minutes % 60) as i8, 0)
+            .map_err(|_| unsupported(&datetime))?;
+        let local = time::PrimitiveDateTime::new(
+            time::Date::try_from(datetime.date.clone())?,
+            time::Time::try_from(datetime.time.clone())?,
+        );
+        Ok(local.assume_offset(offset))
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<Value> for time::Time {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<time::Time, ConversionError> {
+        Ok(time::Time::try_from(Time::try_from(value)?)?)
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<Value> for time::OffsetDateTime {
+    type Error = ConversionError;
+
+    fn try_from(value: Value) -> Result<time::OffsetDateTime, ConversionError> {
+        Ok(time::OffsetDateTime::try_from(DateTime::try_from(value)?)?)
+    }
+}
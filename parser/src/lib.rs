@@ -1,4 +1,10 @@
 extern crate pest;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "time")]
+extern crate time;
+#[cfg(feature = "preserve_order")]
+extern crate indexmap;
 #[macro_use]
 extern crate pest_derive;
 #[cfg(test)]
@@ -8,8 +14,18 @@ mod value;
 #[cfg(test)]
 mod tests;
 mod errors;
+#[cfg(any(feature = "chrono", feature = "time"))]
+mod interop;
 mod parser;
+mod query;
+mod reference;
+mod render;
+mod scanner;
+mod serializer;
 
-pub use errors::Error;
-pub use parser::{parse_file, parse_str};
-pub use value::{Value, Dict, Date};
+pub use errors::{ConversionError, Diagnostic, Error, Location, PathError, SerializeError, Severity, TypeError};
+pub use parser::{check_file, check_str, parse_file, parse_str};
+pub use query::{get, get_mut, remove, select, set};
+pub use render::Renderer;
+pub use serializer::{to_string, Serializer};
+pub use value::{Value, Array, Dict, DictExt, ByteSize, ByteUnit, Date, DateError, DateTime, Time, Weekday};
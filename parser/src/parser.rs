@@ -1,13 +1,22 @@
-use std::path::Path;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
 use std::env;
 use std::fs::File;
 use std::io::prelude::*;
+use std::mem;
+use std::time::Duration;
 
-use pest::Parser;
+use pest::{Error as PestError, Parser, Span};
 use pest::iterators::Pair;
 
-use errors::Error;
-use value::{Date, Dict, Value};
+use errors::{Diagnostic, Error, Location, Severity};
+use query::{self, Segment};
+use reference::{self, Deferred, Part, Pending, Reference};
+use scanner;
+use serializer;
+use value::{ByteSize, ByteUnit, Dict, Value};
 
 
 // This include forces recompiling this source file if the grammar file changes.
@@ -20,6 +29,43 @@ const _GRAMMAR: &str = include_str!("scl.pest");
 #[grammar = "scl.pest"]
 pub struct SclParser;
 
+/// How deep arrays/dicts can be nested, as each level is a recursive call in pest
+pub(crate) const MAX_NESTING: usize = 128;
+
+
+/// The document being parsed, with where its lines start to find the
+/// line and column of an offset without going through the whole document
+#[derive(Debug, PartialEq, Default)]
+struct Source {
+    text: String,
+    /// The offset of the start of each line and whether the line is only ASCII,
+    /// in which case columns are byte offsets
+    lines: Vec<(usize, bool)>,
+}
+
+impl Source {
+    fn new(text: &str) -> Source {
+        let mut start = 0;
+        let lines = text
+            .split('\n')
+            .map(|line| {
+                let l = (start, line.is_ascii());
+                start += line.len() + 1;
+                l
+            })
+            .collect();
+        Source { text: text.to_string(), lines }
+    }
+
+    /// The 1-based line and column of a byte offset
+    fn line_col(&self, offset: usize) -> (usize, usize) {
+        // The first line always starts at 0 so there is at least one line before the offset
+        let line = self.lines.partition_point(|&(start, _)| start <= offset);
+        let (start, ascii) = self.lines[line - 1];
+        let column = if ascii { offset - start } else { self.text[start..offset].chars().count() };
+        (line, column + 1)
+    }
+}
 
 /// A struct that keeps the state of the current file being parsed
 /// in order for the include to work and for the errors to point
@@ -27,14 +73,192 @@ pub struct SclParser;
 /// It is also used when parsing a string.
 #[derive(Debug, PartialEq, Default)]
 struct ParserState<'a> {
+    /// When set, we keep going after an error and every problem found is pushed there
+    diagnostics: Option<RefCell<Vec<Diagnostic>>>,
     /// If the path is `None`, we're parsing a string and the include
     /// should just resolve in whatever directory we're in
     path: Option<&'a Path>,
+    /// The canonical paths of all the files being parsed, from the root
+    /// document to the current one, to detect include cycles
+    stack: Vec<PathBuf>,
+    /// Where the value being parsed goes, from the root document
+    position: RefCell<Vec<Segment>>,
+    /// The values waiting for the references in them to be resolved
+    references: RefCell<Vec<Pending>>,
+    /// The document being parsed, which changes when recovering from syntax errors
+    source: RefCell<Source>,
 }
 
 impl<'a> ParserState<'a> {
-    // TODO: error on different cast/default type
-    fn parse_env_var(&self, pair: Pair<Rule>) -> Value {
+    /// Records the error if we are collecting diagnostics, or returns it so the parsing stops
+    fn report(&self, error: Error) -> Result<(), Error> {
+        match self.diagnostics {
+            Some(ref d) => {
+                d.borrow_mut().push(Diagnostic { severity: Severity::Error, error });
+                Ok(())
+            }
+            None => Err(error),
+        }
+    }
+
+    /// Records a warning if we are collecting diagnostics
+    fn warn(&self, error: Error) {
+        if let Some(ref d) = self.diagnostics {
+            d.borrow_mut().push(Diagnostic { severity: Severity::Warning, error });
+        }
+    }
+
+    /// Builds the location of the given span in the current document
+    fn location(&self, span: &Span) -> Location {
+        let (line, column) = self.source.borrow().line_col(span.start());
+        Location {
+            path: self.path.map(|p| p.to_path_buf()),
+            span: (span.start(), span.end()),
+            line,
+            column,
+        }
+    }
+
+    /// Location of the part of `span` between the offsets `start` and `end` of its text
+    fn location_in(&self, span: &Span, start: usize, end: usize) -> Location {
+        let (line, column) = self.source.borrow().line_col(span.start() + start);
+        Location {
+            path: self.path.map(|p| p.to_path_buf()),
+            span: (span.start() + start, span.start() + end),
+            line,
+            column,
+        }
+    }
+
+    /// Reads a basic or literal string, replacing the escape sequences and interpolating
+    /// the environment variables of basic ones. The references they interpolate are
+    /// kept as parts of their own.
+    fn parse_string(&self, pair: Pair<Rule>) -> Result<Vec<Part>, Error> {
+        if pair.as_rule() == Rule::literal_string {
+            return Ok(vec![Part::Text(unquote(pair.as_str(), 1).to_string())]);
+        }
+
+        let mut parts = Vec::new();
+        for p in pair.into_inner() {
+            match p.as_rule() {
+                Rule::string_text => {
+                    let span = p.into_span();
+                    let text = unescape(span.as_str()).map_err(|e| Error::InvalidValue {
+                        message: e.message,
+                        location: self.location_in(&span, e.start, e.end),
+                    })?;
+                    reference::push_text(&mut parts, &text);
+                }
+                Rule::reference_interpolation => {
+                    let reference = self.parse_reference(p.into_inner().next().unwrap())?;
+                    parts.push(Part::Reference(reference));
+                }
+                Rule::env_var => reference::push_text(&mut parts, &self.interpolate(p)?),
+                _ => unreachable!("unknown string rule: {:?}", p.as_rule()),
+            }
+        }
+        Ok(parts)
+    }
+
+    /// Gives the value of a string, which is only known once the document is parsed
+    /// if it interpolates references
+    fn string_value(&self, parts: Vec<Part>) -> Value {
+        match parts.as_slice() {
+            [] => Value::String(String::new()),
+            [Part::Text(text)] => Value::String(text.clone()),
+            _ => {
+                self.defer(Deferred::String(parts));
+                Value::String(String::new())
+            }
+        }
+    }
+
+    /// Reads a multiline string, only basic ones having line continuations and interpolations
+    fn parse_multiline_string(&self, pair: Pair<Rule>) -> Result<Vec<Part>, Error> {
+        let basic = pair.as_rule() == Rule::multiline_string;
+        let delimiter = if basic { "\"\"\"" } else { "'''" };
+        let span = pair.clone().into_span();
+        let offset = |p: &Pair<Rule>| p.clone().into_span().start() - span.start();
+
+        let mut chunks = Vec::new();
+        if basic {
+            for p in pair.into_inner() {
+                match p.as_rule() {
+                    Rule::multiline_string_text => {
+                        let start = offset(&p);
+                        let text = p.as_str();
+                        let mut chars = text.char_indices();
+                        while let Some((i, c)) = chars.next() {
+                            // `$${` is `${`
+                            if text[i..].starts_with("$${") {
+                                chars.next();
+                            }
+                            chunks.push(Chunk::Char(start + i, c));
+                        }
+                    }
+                    Rule::reference_interpolation => {
+                        let reference = self.parse_reference(p.clone().into_inner().next().unwrap())?;
+                        chunks.push(Chunk::Value(offset(&p), Part::Reference(reference)));
+                    }
+                    Rule::env_var => chunks.push(Chunk::Value(offset(&p), Part::Text(self.interpolate(p)?))),
+                    _ => unreachable!("unknown multiline string rule: {:?}", p.as_rule()),
+                }
+            }
+        } else {
+            chunks.extend(unquote(span.as_str(), 3).char_indices().map(|(i, c)| Chunk::Char(i + 3, c)));
+        }
+
+        multiline(chunks, delimiter, basic).map_err(|e| Error::InvalidValue {
+            message: e.message,
+            location: self.location_in(&span, e.start, e.end),
+        })
+    }
+
+    /// Reads an environment variable in a string, writing its value as SCL if it isn't a string
+    fn interpolate(&self, pair: Pair<Rule>) -> Result<String, Error> {
+        match self.parse_env_var(pair)? {
+            Value::String(s) => Ok(s),
+            value => Ok(serializer::value_to_string(&value)),
+        }
+    }
+
+    /// Reads a `&path` reference
+    fn parse_reference(&self, pair: Pair<Rule>) -> Result<Reference, Error> {
+        let location = self.location(&pair.clone().into_span());
+        // The grammar only lets valid paths through but an index can be too large
+        let path = query::parse_path(&pair.as_str()[1..])
+            .map_err(|e| Error::InvalidValue { message: e.message, location: location.clone() })?;
+        Ok(Reference { path, location, includes: vec![] })
+    }
+
+    /// Records a value to fill in once the references are resolved, at the current position
+    fn defer(&self, value: Deferred) {
+        let position = self.position.borrow().clone();
+        self.references.borrow_mut().push(Pending { position, value });
+    }
+
+    /// Drops the values recorded before `mark` in the value of `key` of the current dict,
+    /// as it is being replaced
+    fn forget_references(&self, key: &str, mark: usize) {
+        let mut position = self.position.borrow().clone();
+        position.push(Segment::Key(key.to_string()));
+        let mut index = 0;
+        self.references.borrow_mut().retain(|p| {
+            index += 1;
+            index > mark || !p.position.starts_with(&position)
+        });
+    }
+
+    /// Parses a value that goes at `segment` in the current dict or array
+    fn parse_value_at(&self, pair: Pair<Rule>, segment: Segment) -> Result<Value, Error> {
+        self.position.borrow_mut().push(segment);
+        let res = self.parse_value(pair);
+        self.position.borrow_mut().pop();
+        res
+    }
+
+    fn parse_env_var(&self, pair: Pair<Rule>) -> Result<Value, Error> {
+        let location = self.location(&pair.clone().into_span());
         let mut key = None;
         let mut cast = None;
         let mut default = None;
@@ -45,115 +269,225 @@ impl<'a> ParserState<'a> {
                     key = Some(p.into_span().as_str().to_string());
                 },
                 Rule::env_var_cast => {
-                    cast = Some(p.into_span().as_str().to_string());
+                    cast = Some((p.as_str().to_string(), self.location(&p.into_span())));
                 },
                 _ => {
-                    default = Some(self.parse_value(p));
+                    // References are resolved after parsing, too late to know whether the default is used
+                    if let Some(r) = p.clone().into_inner().flatten().find(|r| r.as_rule() == Rule::reference) {
+                        return Err(Error::InvalidValue {
+                            message: "references can't be used in the default value of an environment variable".to_string(),
+                            location: self.location(&r.into_span()),
+                        });
+                    }
+                    let default_location = self.location(&p.clone().into_span());
+                    default = Some((self.parse_value(p)?, default_location));
                 }
             };
         }
 
-        if let Some(ref c) = cast {
-            if let Some(ref d) = default {
-                if c != d.type_str() {
-                    panic!("TO IMPLEMENT: error on different cast/default type")
-                }
+        // The grammar ensures we always have a key
+        let name = key.unwrap();
+
+        if let (Some((c, cast_location)), Some((d, default_location))) = (cast.as_ref(), default.as_ref()) {
+            if c != d.type_str() {
+                return Err(Error::TypeMismatch {
+                    expected: cast_type_str(c),
+                    found: d.type_str(),
+                    location: default_location.clone(),
+                    origin: Some(Box::new(cast_location.clone())),
+                });
             }
         }
 
-        match env::var(&key.unwrap()) {
+        match env::var(&name) {
             Ok(s) => {
-                if let Some(c) = cast {
-                    // TODO: error handling
-                    match c.as_str() {
-                        "integer" => Value::Integer(s.parse().unwrap()),
-                        "float" => Value::Float(s.parse().unwrap()),
-                        "bool" => Value::Boolean(s.parse().unwrap()),
-                        "date" => Value::Date(Date::from_str(&c)),
-                        _ => unreachable!()
-                    }
+                if let Some((c, _)) = cast {
+                    cast_env_var(s, &c).map_err(|value| Error::InvalidCast { value, cast: c, location })
                 } else {
-                    Value::String(s)
+                    Ok(Value::String(s))
                 }
             },
-            Err(_) => default.unwrap(),
+            Err(env::VarError::NotPresent) => match default {
+                Some((d, _)) => Ok(d),
+                None => Err(Error::EnvVar { name, message: "is not set".to_string(), location }),
+            },
+            Err(env::VarError::NotUnicode(_)) => {
+                Err(Error::EnvVar { name, message: "is not valid unicode".to_string(), location })
+            }
         }
     }
 
-    // TODO: return an error if types are different
-    fn parse_array(&self, pair: Pair<Rule>) -> Value {
-        let mut items = vec![];
+    fn parse_array(&self, pair: Pair<Rule>) -> Result<Value, Error> {
+        let mut items: Vec<Value> = vec![];
+        // The index and location of the first item that isn't a reference, which
+        // are only checked once resolved
+        let mut first: Option<(usize, Location)> = None;
 
         for p in pair.into_inner() {
             // we can only have Rule::Value here, no need to match
-            let val = self.parse_value(p.into_inner().next().unwrap());
-            if let Some(last) = items.last() {
-                if !val.same_type(last) {
-                    // TODO: return an error
+            let location = self.location(&p.clone().into_span());
+            let inner = p.into_inner().next().unwrap();
+            let is_reference = inner.as_rule() == Rule::reference;
+            let mark = self.references.borrow().len();
+            let val = match self.parse_value_at(inner, Segment::Index(items.len())) {
+                Ok(v) => v,
+                Err(e) => {
+                    self.references.borrow_mut().truncate(mark);
+                    self.report(e)?;
+                    continue;
                 }
+            };
+            if is_reference {
+                items.push(val);
+                continue;
+            }
+            if let Some((index, ref first_location)) = first {
+                let first_item = &items[index];
+                if !val.same_type(first_item) {
+                    self.references.borrow_mut().truncate(mark);
+                    self.report(Error::TypeMismatch {
+                        expected: first_item.type_str(),
+                        found: val.type_str(),
+                        location,
+                        origin: Some(Box::new(first_location.clone())),
+                    })?;
+                    continue;
+                }
+            } else {
+                first = Some((items.len(), location));
             }
             items.push(val);
         }
 
-        Value::Array(items)
+        Ok(Value::Array(items))
     }
 
-    fn parse_byte_size(&self, pair: Pair<Rule>) -> Value {
-        let mut num: Option<f64> = None;
+    fn parse_byte_size(&self, pair: Pair<Rule>) -> Result<Value, Error> {
+        let span = pair.clone().into_span();
+        let mut inner = pair.into_inner();
+        // The grammar only lets a number followed by a unit through
+        let number = inner.next().unwrap().as_str();
+        let unit = inner.next().unwrap().as_str();
 
-        for p in pair.into_inner() {
-            match p.as_rule() {
-                Rule::byte_size_number => {
-                    num = Some(p.as_str().parse().unwrap());
-                }
-                Rule::byte_size_unit => {
-                    let n = num.unwrap();
-                    let res = match p.as_str() {
-                        "kB" | "KB" => n * 1e3,
-                        "MB" => n * 1e6,
-                        "GB" => n * 1e9,
-                        "TB" => n * 1e12,
-                        "PB" => n * 1e15,
-                        _ => unreachable!(),
-                    };
-
-                    return Value::Integer(res as i64);
-                }
-                _ => unreachable!(),
-            }
+        // The grammar only lets known units through
+        let unit = ByteUnit::from_suffix(unit).unwrap();
+
+        match byte_size_to_bytes(number, unit) {
+            Ok(bytes) => Ok(Value::ByteSize(ByteSize::new(bytes, unit))),
+            Err(ByteSizeError::Fractional) => Err(Error::InvalidValue {
+                message: format!("`{}` is not a whole number of bytes", span.as_str()),
+                location: self.location(&span),
+            }),
+            Err(ByteSizeError::Overflow) => Err(Error::OutOfRange {
+                value: span.as_str().to_string(),
+                type_name: "byte size",
+                location: self.location(&span),
+            }),
         }
+    }
 
-        unreachable!("Got a byte size without a unit?")
+    fn parse_duration(&self, pair: Pair<Rule>) -> Result<Value, Error> {
+        match parse_duration(pair.as_str()) {
+            Ok(d) => Ok(Value::Duration(d)),
+            Err(DurationError::Overflow) => Err(Error::OutOfRange {
+                value: pair.as_str().to_string(),
+                type_name: "duration",
+                location: self.location(&pair.into_span()),
+            }),
+            Err(DurationError::Unordered) => Err(Error::InvalidValue {
+                message: "the units of a duration must go from the largest to the smallest, each used once".to_string(),
+                location: self.location(&pair.into_span()),
+            }),
+            Err(DurationError::Malformed) => unreachable!("The grammar only lets valid durations through"),
+        }
     }
 
-    fn parse_value(&self, pair: Pair<Rule>) -> Value {
-        match pair.as_rule() {
-            Rule::int => Value::Integer(pair.as_str().parse().unwrap()),
-            Rule::float => Value::Float(pair.as_str().parse().unwrap()),
-            Rule::byte_size => self.parse_byte_size(pair),
+    fn parse_value(&self, pair: Pair<Rule>) -> Result<Value, Error> {
+        let value = match pair.as_rule() {
+            Rule::int => match parse_integer(pair.as_str()) {
+                Some(i) => Value::integer(i),
+                // The grammar only lets valid integers through so it can only be an overflow
+                None => {
+                    return Err(Error::OutOfRange {
+                        value: pair.as_str().to_string(),
+                        type_name: "integer",
+                        location: self.location(&pair.into_span()),
+                    });
+                }
+            },
+            Rule::float => {
+                let text = pair.as_str().replace('_', "");
+                // The grammar only lets valid floats through, which Rust can parse
+                let float: f64 = text.parse().unwrap();
+                // Too large numbers become infinity, only `inf` should
+                if float.is_infinite() && !text.ends_with("inf") {
+                    return Err(Error::OutOfRange {
+                        value: pair.as_str().to_string(),
+                        type_name: "float",
+                        location: self.location(&pair.into_span()),
+                    });
+                }
+                Value::Float(float)
+            }
+            Rule::byte_size => self.parse_byte_size(pair)?,
+            Rule::duration => self.parse_duration(pair)?,
             Rule::boolean => match pair.as_str() {
                 "true" => Value::Boolean(true),
                 "false" => Value::Boolean(false),
                 _ => unreachable!(),
             },
-            Rule::string => Value::String(pair.as_str().replace("\"", "").to_string()),
-            Rule::multiline_string => {
-                let text = pair.as_str().replace("\"\"\"", "");
-                if text.starts_with('\n') {
-                    Value::String(text.trim_left().to_string())
-                } else {
-                    Value::String(text.to_string())
-                }
+            Rule::string | Rule::literal_string => {
+                let parts = self.parse_string(pair)?;
+                self.string_value(parts)
             }
-            Rule::env_var => self.parse_env_var(pair),
-            Rule::date => Value::Date(Date::from_str(pair.as_str())),
-            Rule::array => self.parse_array(pair),
-            Rule::dict => Value::Dict(self.parse_dict(pair).unwrap()), // todo: error handling
+            Rule::multiline_string | Rule::multiline_literal_string => {
+                let parts = self.parse_multiline_string(pair)?;
+                self.string_value(parts)
+            }
+            Rule::env_var => self.parse_env_var(pair)?,
+            Rule::reference => {
+                let reference = self.parse_reference(pair)?;
+                self.defer(Deferred::Reference(reference));
+                // A placeholder until the references are resolved
+                Value::Boolean(false)
+            }
+            // The grammar only lets well-formed dates and times through but they might not exist or be out of range
+            Rule::date => match pair.as_str().parse() {
+                Ok(d) => Value::Date(d),
+                Err(e) => {
+                    return Err(Error::InvalidValue {
+                        message: e.to_string(),
+                        location: self.location(&pair.into_span()),
+                    });
+                }
+            },
+            Rule::datetime => match pair.as_str().parse() {
+                Ok(d) => Value::DateTime(d),
+                Err(e) => {
+                    return Err(Error::InvalidValue {
+                        message: e.to_string(),
+                        location: self.location(&pair.into_span()),
+                    });
+                }
+            },
+            Rule::time => match pair.as_str().parse() {
+                Ok(t) => Value::Time(t),
+                Err(e) => {
+                    return Err(Error::InvalidValue {
+                        message: e.to_string(),
+                        location: self.location(&pair.into_span()),
+                    });
+                }
+            },
+            Rule::array => self.parse_array(pair)?,
+            Rule::dict => Value::Dict(self.parse_dict(pair)?),
             _ => unreachable!("Got an unexpected value: {:?}", pair),
-        }
+        };
+
+        Ok(value)
     }
 
-    fn parse_key_value(&self, pair: Pair<Rule>) -> (String, Value) {
+    fn parse_key_value(&self, pair: Pair<Rule>) -> Result<(String, Value), Error> {
         let mut key = None;
         let mut value = None;
 
@@ -164,30 +498,62 @@ impl<'a> ParserState<'a> {
                 }
                 // The grammar made sure we can only have one value or an include
                 Rule::value => {
-                    value = Some(self.parse_value(p.into_inner().next().unwrap()));
+                    value = Some(self.parse_value(p.into_inner().next().unwrap())?);
                 }
                 Rule::include => {
-                    value = Some(Value::Dict(self.parse_include(p).unwrap())); // TODO: error handling
+                    value = Some(Value::Dict(self.parse_include(p)?));
                 }
                 _ => unreachable!("Got something in key/value other than a key/value: {:?}", p),
             };
         }
 
-        (key.unwrap(), value.unwrap())
+        Ok((key.unwrap(), value.unwrap()))
     }
 
     fn parse_dict(&self, pair: Pair<Rule>) -> Result<Dict, Error> {
         let mut dict = Dict::new();
+        // Where each key was set, to point at the first one on duplicates
+        let mut key_locations: HashMap<String, Location> = HashMap::new();
 
         for p in pair.into_inner() {
+            // The values being replaced must not be filled in once the references are resolved
+            let mark = self.references.borrow().len();
             match p.as_rule() {
-                Rule::include => {
-                    let included = self.parse_include(p)?;
-                    dict.extend(included);
-                }
+                Rule::include => match self.parse_include(p) {
+                    Ok(included) => {
+                        for key in included.keys().filter(|k| dict.contains_key(*k)) {
+                            self.forget_references(key, mark);
+                        }
+                        dict.extend(included)
+                    }
+                    Err(e) => self.report(e)?,
+                },
                 Rule::key_value => {
-                    let (key, value) = self.parse_key_value(p);
-                    dict.insert(key, value);
+                    // The first inner token of a key/value is always the key
+                    let key_pair = p.clone().into_inner().next().unwrap();
+                    let key_location = self.location(&key_pair.clone().into_span());
+                    self.position.borrow_mut().push(Segment::Key(key_pair.as_str().to_string()));
+                    let res = self.parse_key_value(p);
+                    self.position.borrow_mut().pop();
+                    match res {
+                        Ok((key, value)) => {
+                            if let Some(previous) = key_locations.insert(key.clone(), key_location.clone()) {
+                                self.warn(Error::DuplicateKey {
+                                    key: key.clone(),
+                                    location: key_location,
+                                    previous: Box::new(previous),
+                                });
+                            }
+                            if dict.contains_key(&key) {
+                                self.forget_references(&key, mark);
+                            }
+                            dict.insert(key, value);
+                        }
+                        Err(e) => {
+                            self.references.borrow_mut().truncate(mark);
+                            self.report(e)?
+                        }
+                    }
                 }
                 _ => unreachable!("unknown dict rule: {:?}", p.as_rule()),
             }
@@ -197,85 +563,655 @@ impl<'a> ParserState<'a> {
     }
 
     fn parse_include(&self, pair: Pair<Rule>) -> Result<Dict, Error> {
-        // next inner token is the filename
-        let path = pair.into_inner()
-            .next()
-            .unwrap()
-            .into_span()
-            .as_str()
-            .replace("\"", "");
+        let location = self.location(&pair.clone().into_span());
+        // next inner token is the filename, which is needed right away
+        let path = match self.parse_string(pair.into_inner().next().unwrap())?.pop() {
+            None => String::new(),
+            Some(Part::Text(path)) => path,
+            Some(Part::Reference(r)) => {
+                return Err(Error::InvalidValue {
+                    message: "references can't be used in the path of an include".to_string(),
+                    location: r.location,
+                });
+            }
+        };
 
         // we have to deal wih an include
         // - if we do not have a current path, just call `parse_file`, we can't
         // give any context
         // - if we have one, first join the current dir and the filename
-
-        if let Some(current_path) = self.path {
+        let full_path = match self.path {
             // if the path is absolute, don't append the current path to it
-            if path.starts_with('/') {
-                parse_file(path)
-            } else {
-                // TODO: error handling for parent()?
-                let full_path = current_path.parent().unwrap().join(path);
-                parse_file(&full_path)
+            Some(current_path) if !path.starts_with('/') => match current_path.parent() {
+                Some(parent) => parent.join(path),
+                None => PathBuf::from(path),
+            },
+            _ => PathBuf::from(path),
+        };
+
+        // An included file that can't be read is reported at the `include` itself,
+        // other errors keep track of the `include` that led to them
+        let canonical = full_path.canonicalize().map_err(|e| Error::Include {
+            path: full_path.clone(),
+            message: e.to_string(),
+            location: location.clone(),
+        })?;
+        if self.stack.contains(&canonical) {
+            return Err(Error::Include {
+                path: full_path,
+                message: "the file is already being included".to_string(),
+                location,
+            });
+        }
+
+        let contents = read_file(&full_path).map_err(|e| match e {
+            Error::Io { path, message } => Error::Include { path, message, location: location.clone() },
+            e => e,
+        })?;
+
+        let mut stack = self.stack.clone();
+        stack.push(canonical);
+        let state = ParserState {
+            path: Some(&full_path),
+            stack,
+            diagnostics: self.diagnostics.as_ref().map(|_| RefCell::new(vec![])),
+            position: RefCell::new(self.position.borrow().clone()),
+            references: RefCell::new(vec![]),
+            source: RefCell::default(),
+        };
+        let res = state.parse_str(&contents);
+
+        if let (Some(ours), Some(theirs)) = (self.diagnostics.as_ref(), state.diagnostics) {
+            ours.borrow_mut().extend(theirs.into_inner().into_iter().map(|d| {
+                d.map(|e| Error::Included { location: location.clone(), cause: Box::new(e) })
+            }));
+        }
+        if res.is_ok() {
+            let pending = state.references.into_inner().into_iter().map(|p| p.included_from(&location));
+            self.references.borrow_mut().extend(pending);
+        }
+
+        res.map_err(|e| Error::Included { location, cause: Box::new(e) })
+    }
+
+    /// Builds the location of a byte offset in the given input, for errors
+    /// happening outside of the grammar
+    fn location_at(&self, input: &str, offset: usize) -> Location {
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Location {
+            path: self.path.map(|p| p.to_path_buf()),
+            span: (offset, offset + 1),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    /// Turns an error from the grammar into one of our errors, making the rules
+    /// names understandable
+    fn syntax_error(&self, e: PestError<Rule>) -> Error {
+        let fancy_e = e.renamed_rules(|rule| {
+            match *rule {
+                Rule::document => "a key value, an include or a comment".to_string(),
+                Rule::key => "a key".to_string(),
+                Rule::boolean => "a boolean (true / false)".to_string(),
+                Rule::string => "a string".to_string(),
+                Rule::multiline_string => "a multiline string".to_string(),
+                Rule::literal_string => "a literal string".to_string(),
+                Rule::multiline_literal_string => "a multiline literal string".to_string(),
+                Rule::string_text => "the text of a string".to_string(),
+                Rule::multiline_string_text => "the text of a multiline string".to_string(),
+                Rule::int => "an integer".to_string(),
+                Rule::float => "a float".to_string(),
+                Rule::date => "a date".to_string(),
+                Rule::datetime => "a datetime".to_string(),
+                Rule::time => "a time".to_string(),
+                Rule::key_value => "a key value".to_string(),
+                Rule::byte_size_unit => "a byte size unit (kB / MB / GB / TB / PB / KiB / MiB / GiB / TiB / PiB)".to_string(),
+                Rule::value => "string / int / float / byte size / duration / date / datetime / time / bool / array / dict / environment variable / reference".to_string(),
+                Rule::include => "include".to_string(),
+                Rule::byte_size_number => "a number".to_string(),
+                Rule::env_var => "an environment variable".to_string(),
+                Rule::reference => "a reference (&key.path)".to_string(),
+                Rule::reference_interpolation => "an interpolated reference".to_string(),
+                Rule::env_var_cast => "a cast to integer/float/date/datetime/time/duration/bool".to_string(),
+                Rule::array => "an array".to_string(),
+                Rule::dict => "a dictionary".to_string(),
+                Rule::byte_size => "a byte size".to_string(),
+                Rule::duration_number => "a number".to_string(),
+                Rule::duration_unit => "a duration unit (ns / us / ms / s / m / h / d)".to_string(),
+                Rule::duration => "a duration".to_string(),
+                Rule::literal => "a boolean, a string, a number, a duration, a date or a time".to_string(),
+                Rule::digits => "a digit".to_string(),
+                Rule::exponent => "an exponent".to_string(),
+                Rule::sign => "a sign (+ / -)".to_string(),
+                Rule::hex_digit => "a hexadecimal digit".to_string(),
+                Rule::hex_int => "a hexadecimal integer".to_string(),
+                Rule::oct_int => "an octal integer".to_string(),
+                Rule::bin_int => "a binary integer".to_string(),
+                Rule::year => "a year".to_string(),
+                Rule::month => "a month (01 to 12)".to_string(),
+                Rule::day => "a day (01 to 31)".to_string(),
+                Rule::hour => "an hour (00 to 23)".to_string(),
+                Rule::minute => "a minute (00 to 59)".to_string(),
+                Rule::second => "a second (00 to 59)".to_string(),
+                Rule::time_offset => "a timezone offset (Z or +HH:MM)".to_string(),
+                Rule::whitespace => "a space".to_string(),
+                Rule::line_end => "a new line".to_string(),
+                Rule::comments => "a comment".to_string(),
             }
-        } else {
-            parse_file(path)
+        });
+
+        match fancy_e {
+            PestError::CustomErrorPos { message, pos } => {
+                Error::InvalidSyntax { message, location: self.location(&pos.span(&pos)) }
+            }
+            _ => unreachable!("Renaming rules always gives a CustomErrorPos"),
         }
     }
 
-    /// Parse the given string
+    /// Parses the given string, stopping at the first syntax error
+    fn parse_document(&self, input: &str) -> Result<Dict, Error> {
+        if let Some(offset) = scanner::find_excessive_nesting(input, MAX_NESTING) {
+            return Err(Error::InvalidSyntax {
+                message: format!("arrays and dictionaries can only be nested {} levels deep", MAX_NESTING),
+                location: self.location_at(input, offset),
+            });
+        }
+
+        *self.source.borrow_mut() = Source::new(input);
+        let mut pairs = SclParser::parse(Rule::document, input).map_err(|e| self.syntax_error(e))?;
+
+        // We must have at least a `document` pair if we got there
+        self.parse_dict(pairs.next().unwrap())
+    }
+
+    /// Parse the given string.
+    /// When collecting diagnostics, the statements with syntax errors are skipped
+    /// until we can parse the rest of the document.
     pub fn parse_str(&self, input: &str) -> Result<Dict, Error> {
-        let mut pairs = match SclParser::parse(Rule::document, input) {
-            Ok(p) => p,
-            Err(e) => {
-                let fancy_e = e.renamed_rules(|rule| {
-                    match *rule {
-                        Rule::document => "a key value, an include or a comment".to_string(),
-                        Rule::key => "a key".to_string(),
-                        Rule::boolean => "a boolean (true / false)".to_string(),
-                        Rule::string => "a string".to_string(),
-                        Rule::multiline_string => "a multiline string".to_string(),
-                        Rule::int => "an integer".to_string(),
-                        Rule::float => "a float".to_string(),
-                        Rule::date => "a date".to_string(),
-                        Rule::key_value => "a key value".to_string(),
-                        Rule::byte_size_unit => "a byte size unit (kB / MB / GB / TB / PB)".to_string(),
-                        Rule::value => "string / int / float / byte size / date / bool / array / dict / environment variable".to_string(),
-                        Rule::include => "include".to_string(),
-                        Rule::byte_size_number => "a number".to_string(),
-                        Rule::env_var => "an environment variable".to_string(),
-                        Rule::env_var_cast => "a cast to integer/float/date/bool".to_string(),
-                        Rule::array => "an array".to_string(),
-                        Rule::dict => "a dictionary".to_string(),
-                        _ => format!("TODO: {:?}", rule),
+        let mut input = input.to_string();
+
+        loop {
+            let error = match self.parse_document(&input) {
+                Err(e @ Error::InvalidSyntax { .. }) => e,
+                res => return res,
+            };
+            if self.diagnostics.is_none() {
+                return Err(error);
+            }
+
+            // Syntax errors always have a location
+            let offset = error.location().unwrap().span.0;
+            self.report(error)?;
+            match scanner::recovery_range(&input, offset) {
+                Some(range) => input = scanner::blank(&input, range),
+                None => return Ok(Dict::new()),
+            }
+        }
+    }
+
+    /// Parses the root document, filling in the references once all the includes are merged
+    fn parse_root(&self, input: &str) -> Result<Dict, Error> {
+        let mut dict = self.parse_str(input)?;
+        let pending = mem::take(&mut *self.references.borrow_mut());
+        for error in reference::resolve(&mut dict, &pending) {
+            self.report(error)?;
+        }
+        Ok(dict)
+    }
+
+}
+
+/// Returns the type name matching an env var cast, as returned by `Value::type_str`
+fn cast_type_str(cast: &str) -> &'static str {
+    match cast {
+        "integer" => "integer",
+        "float" => "float",
+        "bool" => "bool",
+        "date" => "date",
+        "datetime" => "datetime",
+        "time" => "time",
+        "duration" => "duration",
+        _ => unreachable!("Unknown env var cast: {}", cast),
+    }
+}
+
+/// Reads decimal, hexadecimal (`0x`), octal (`0o`) and binary (`0b`) integers with
+/// an optional sign and underscores, returning `None` if it's not one or it overflows
+fn parse_integer(text: &str) -> Option<i128> {
+    let text = text.replace('_', "");
+    let (negative, unsigned) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, &text[..]),
+    };
+    let (radix, digits) = match unsigned.get(..2) {
+        Some("0x") => (16, &unsigned[2..]),
+        Some("0o") => (8, &unsigned[2..]),
+        Some("0b") => (2, &unsigned[2..]),
+        _ => (10, unsigned),
+    };
+    // `from_str_radix` would accept another sign
+    if digits.starts_with('+') || digits.starts_with('-') {
+        return None;
+    }
+
+    // Parsing with the sign handles `i128::MIN`
+    let signed = if negative { format!("-{}", digits) } else { digits.to_string() };
+    i128::from_str_radix(&signed, radix).ok()
+}
+
+/// Removes the `quotes` delimiters on each side of a string
+fn unquote(text: &str, quotes: usize) -> &str {
+    &text[quotes..text.len() - quotes]
+}
+
+/// An invalid part of a string, like an unknown escape, with its offsets in the string
+#[derive(Debug, PartialEq)]
+struct StringError {
+    start: usize,
+    end: usize,
+    message: String,
+}
+
+/// Replaces the escape sequences in the text of a basic string, including `$${` for `${`
+fn unescape(text: &str) -> Result<String, StringError> {
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while let Some(c) = text[i..].chars().next() {
+        if text[i..].starts_with("$${") {
+            out.push_str("${");
+            i += 3;
+            continue;
+        }
+        if c != '\\' {
+            out.push(c);
+            i += c.len_utf8();
+            continue;
+        }
+
+        // The grammar makes sure a backslash is always followed by a character
+        let escaped = text[i + 1..].chars().next().unwrap();
+        let mut end = i + 1 + escaped.len_utf8();
+        match escaped {
+            '"' => out.push('"'),
+            '\\' => out.push('\\'),
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            'r' => out.push('\r'),
+            'u' => {
+                let hex = text[end..]
+                    .strip_prefix('{')
+                    .and_then(|rest| rest.find('}').map(|close| &rest[..close]))
+                    .filter(|hex| !hex.is_empty() && hex.len() <= 6 && hex.chars().all(|c| c.is_ascii_hexdigit()));
+                let hex = match hex {
+                    Some(hex) => hex,
+                    None => {
+                        return Err(StringError {
+                            start: i,
+                            end,
+                            message: "unicode escapes must be written as `\\u{XXXX}` with 1 to 6 hexadecimal digits".to_string(),
+                        });
+                    }
+                };
+                end += hex.len() + 2;
+                match char::from_u32(u32::from_str_radix(hex, 16).unwrap()) {
+                    Some(c) => out.push(c),
+                    None => {
+                        return Err(StringError {
+                            start: i,
+                            end,
+                            message: format!("`\\u{{{}}}` is not a valid unicode character", hex),
+                        });
                     }
+                }
+            }
+            _ => {
+                return Err(StringError {
+                    start: i,
+                    end,
+                    message: format!(
+                        "`\\{}` is not a valid escape, only \\\", \\\\, \\n, \\t, \\r and \\u{{XXXX}} are allowed",
+                        escaped
+                    ),
                 });
-                return Err(Error::InvalidSyntax(format!("{}", fancy_e)));
             }
+        }
+        i = end;
+    }
+
+    Ok(out)
+}
+
+/// A part of the content of a multiline string, with its offset in the document
+#[derive(Debug)]
+enum Chunk {
+    Char(usize, char),
+    /// An interpolated environment variable or reference
+    Value(usize, Part),
+}
+
+impl Chunk {
+    fn offset(&self) -> usize {
+        match *self {
+            Chunk::Char(offset, _) | Chunk::Value(offset, _) => offset,
+        }
+    }
+
+    fn is(chunk: Option<&Chunk>, c: char) -> bool {
+        matches!(chunk, Some(&Chunk::Char(_, x)) if x == c)
+    }
+}
+
+/// Reads the content of a multiline string closed by `delimiter`:
+///
+/// - the newline right after the opening delimiter is dropped
+/// - if the closing delimiter is on its own line, its indentation is removed from
+///   every line but the one of the opening delimiter. Lines only made of part of
+///   that indentation become empty and any other line less indented is an error
+/// - if `continuation` is set, a backslash at the end of a line removes the newline
+///   and the whitespace and newlines following it
+///
+/// Interpolated values are never changed.
+fn multiline(mut chunks: Vec<Chunk>, delimiter: &str, continuation: bool) -> Result<Vec<Part>, StringError> {
+    let start = if Chunk::is(chunks.first(), '\n') {
+        1
+    } else if Chunk::is(chunks.first(), '\r') && Chunk::is(chunks.get(1), '\n') {
+        2
+    } else {
+        0
+    };
+    let mut indentation = Vec::new();
+    if let Some(i) = chunks.iter().rposition(|c| Chunk::is(Some(c), '\n')) {
+        let last_line = &chunks[i + 1..];
+        if last_line.iter().all(|c| Chunk::is(Some(c), ' ') || Chunk::is(Some(c), '\t')) {
+            indentation = last_line.iter().map(|c| if let Chunk::Char(_, c) = *c { c } else { unreachable!() }).collect();
+            // The newline before the closing delimiter is kept, only its indentation goes
+            chunks.truncate(i + 1);
+        }
+    }
+
+    let body = &chunks[start..];
+    let mut dedented: Vec<&Chunk> = Vec::with_capacity(body.len());
+    let mut line_start = 0;
+    for (i, line) in body.split(|c| Chunk::is(Some(c), '\n')).enumerate() {
+        if i > 0 {
+            dedented.push(&body[line_start - 1]);
+        }
+        if i == 0 && start == 0 {
+            // Text right after the opening delimiter isn't indented
+            dedented.extend(line);
+        } else {
+            dedented.extend(dedent(line, &indentation, delimiter)?);
+        }
+        line_start += line.len() + 1;
+    }
+
+    let mut parts = Vec::new();
+    let mut out = String::with_capacity(dedented.len());
+    let mut i = 0;
+    while i < dedented.len() {
+        let next = |n: usize| dedented.get(i + n).copied();
+        match *dedented[i] {
+            Chunk::Char(_, '\\')
+                if continuation && (Chunk::is(next(1), '\n') || Chunk::is(next(1), '\r') && Chunk::is(next(2), '\n')) =>
+            {
+                i += 1;
+                while matches!(dedented.get(i), Some(&&Chunk::Char(_, ' ' | '\t' | '\r' | '\n'))) {
+                    i += 1;
+                }
+                continue;
+            }
+            Chunk::Char(_, c) => out.push(c),
+            Chunk::Value(_, Part::Text(ref value)) => out.push_str(value),
+            Chunk::Value(_, ref reference) => {
+                reference::push_text(&mut parts, &mem::take(&mut out));
+                parts.push(reference.clone());
+            }
+        }
+        i += 1;
+    }
+    reference::push_text(&mut parts, &out);
+
+    Ok(parts)
+}
+
+/// Removes the indentation from the start of a line of a multiline string
+fn dedent<'a>(line: &'a [Chunk], indentation: &[char], delimiter: &str) -> Result<&'a [Chunk], StringError> {
+    let matches = |chunks: &[Chunk]| chunks.iter().zip(indentation).all(|(c, i)| Chunk::is(Some(c), *i));
+
+    if line.len() >= indentation.len() && matches(&line[..indentation.len()]) {
+        Ok(&line[indentation.len()..])
+    } else if line.len() < indentation.len() && matches(line) {
+        Ok(&[])
+    } else {
+        // An empty line always matches so there is a first chunk
+        let first = &line[0];
+        let len = match *first {
+            Chunk::Char(_, c) => c.len_utf8(),
+            Chunk::Value(..) => 2,
         };
+        Err(StringError {
+            start: first.offset(),
+            end: first.offset() + len,
+            message: format!("this line is less indented than the closing `{}`", delimiter),
+        })
+    }
+}
 
-        // We must have at least a `document` pair if we got there
-        self.parse_dict(pairs.next().unwrap())
+/// Why a byte size can't be turned into a number of bytes
+#[derive(Debug, PartialEq)]
+enum ByteSizeError {
+    /// It has a fraction of a byte, like `1.0000000001GB`
+    Fractional,
+    /// It's more than `u64::MAX` bytes
+    Overflow,
+}
+
+/// How many times `factor` divides `n`
+fn count_factors(mut n: u128, factor: u128) -> u32 {
+    let mut count = 0;
+    while n > 0 && n.is_multiple_of(factor) {
+        n /= factor;
+        count += 1;
+    }
+    count
+}
+
+/// Computes exactly how many bytes `number` (like `1_000.5`) `unit`s are
+fn byte_size_to_bytes(number: &str, unit: ByteUnit) -> Result<u64, ByteSizeError> {
+    let multiplier = u128::from(unit.bytes());
+    let number = number.replace('_', "");
+    let (integer, fraction) = match number.find('.') {
+        Some(i) => (&number[..i], number[i + 1..].trim_end_matches('0')),
+        None => (&number[..], ""),
+    };
+
+    // Too many digits for a u128 means the integer part overflows anyway
+    let bytes = integer
+        .parse::<u128>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or(ByteSizeError::Overflow)?;
+
+    let fraction_bytes = if fraction.is_empty() { 0 } else { fraction_to_bytes(fraction, multiplier)? };
+
+    bytes
+        .checked_add(fraction_bytes)
+        .and_then(|b| u64::try_from(b).ok())
+        .ok_or(ByteSizeError::Overflow)
+}
+
+/// Computes how many bytes `0.<fraction>` times `multiplier` bytes are, `fraction` having
+/// no trailing zeros. It can be any number of digits, like the 50 of 1 byte in PiB.
+fn fraction_to_bytes(fraction: &str, multiplier: u128) -> Result<u128, ByteSizeError> {
+    // `fraction / 10^digits * multiplier` must be whole. Units are only made of 2s and 5s:
+    // once the common factors are removed, the denominator is made of only 2s or only 5s as
+    // a fraction without trailing zeros can't be a multiple of 10, and it has to divide the fraction.
+    let digits = fraction.len() as u32;
+    let (twos, fives) = (count_factors(multiplier, 2), count_factors(multiplier, 5));
+    if digits > twos && digits > fives {
+        return Err(ByteSizeError::Fractional);
+    }
+    let denominator = 2u128.pow(digits.saturating_sub(twos)) * 5u128.pow(digits.saturating_sub(fives));
+    let rest = multiplier / (2u128.pow(twos.min(digits)) * 5u128.pow(fives.min(digits)));
+
+    // Long division as the fraction can have more digits than a u128
+    let (mut quotient, mut remainder) = (0u128, 0u128);
+    for digit in fraction.bytes() {
+        remainder = remainder * 10 + u128::from(digit - b'0');
+        quotient = quotient * 10 + remainder / denominator;
+        remainder %= denominator;
+    }
+    if remainder != 0 {
+        return Err(ByteSizeError::Fractional);
+    }
+    Ok(quotient * rest)
+}
+
+/// Why some text couldn't be read as a duration
+#[derive(Debug, PartialEq)]
+enum DurationError {
+    /// It's not a sequence of numbers followed by units
+    Malformed,
+    /// A unit is repeated or comes after a smaller one, like `30m1h`
+    Unordered,
+    /// It's longer than what a `Duration` can hold
+    Overflow,
+}
+
+/// The units of durations in decreasing order, with how many nanoseconds they are
+pub(crate) const DURATION_UNITS: [(&str, u128); 7] = [
+    ("d", 86_400_000_000_000),
+    ("h", 3_600_000_000_000),
+    ("m", 60_000_000_000),
+    ("s", 1_000_000_000),
+    ("ms", 1_000_000),
+    ("us", 1_000),
+    ("ns", 1),
+];
+
+/// Reads durations like `1h30m` or `250ms`, which are also allowed in env vars
+fn parse_duration(input: &str) -> Result<Duration, DurationError> {
+    let mut rest = input;
+    let mut nanoseconds: u128 = 0;
+    // Index in `DURATION_UNITS` of the last unit seen
+    let mut last_unit = None;
+
+    if rest.is_empty() {
+        return Err(DurationError::Malformed);
     }
+    while !rest.is_empty() {
+        let number_length = rest.bytes().take_while(|b| b.is_ascii_digit() || *b == b'_').count();
+        let number = rest[..number_length].replace('_', "");
+        rest = &rest[number_length..];
+        let unit_length = rest.bytes().take_while(|b| b.is_ascii_lowercase()).count();
+        let unit = DURATION_UNITS.iter().position(|&(u, _)| u == &rest[..unit_length]);
+        rest = &rest[unit_length..];
+
+        let (number, unit) = match (number.parse::<u128>(), unit) {
+            (Ok(n), Some(u)) => (n, u),
+            (Err(_), Some(_)) if !number.is_empty() => return Err(DurationError::Overflow),
+            _ => return Err(DurationError::Malformed),
+        };
+        if last_unit.is_some_and(|last| last >= unit) {
+            return Err(DurationError::Unordered);
+        }
+        last_unit = Some(unit);
+        nanoseconds = number
+            .checked_mul(DURATION_UNITS[unit].1)
+            .and_then(|n| n.checked_add(nanoseconds))
+            .ok_or(DurationError::Overflow)?;
+    }
+
+    let seconds = u64::try_from(nanoseconds / 1_000_000_000).map_err(|_| DurationError::Overflow)?;
+    Ok(Duration::new(seconds, (nanoseconds % 1_000_000_000) as u32))
+}
+
+/// Casts the content of an env var to the given type, giving back the content
+/// if it can't be cast
+fn cast_env_var(content: String, cast: &str) -> Result<Value, String> {
+    let value = match cast {
+        "integer" => parse_integer(&content).map(Value::integer),
+        // Like in the document, only infinity itself can be infinite
+        "float" => content.parse::<f64>().ok().filter(|f| f.is_finite() || spells_infinity(&content)).map(Value::Float),
+        "bool" => content.parse().map(Value::Boolean).ok(),
+        "date" => content.parse().map(Value::Date).ok(),
+        "datetime" => content.parse().map(Value::DateTime).ok(),
+        "time" => content.parse().map(Value::Time).ok(),
+        "duration" => parse_duration(&content).map(Value::Duration).ok(),
+        _ => unreachable!("Unknown env var cast: {}", cast),
+    };
+
+    value.ok_or(content)
+}
 
+/// Whether the text is infinity rather than a number too large for a float
+fn spells_infinity(text: &str) -> bool {
+    let text = text.trim_start_matches(['+', '-']).to_ascii_lowercase();
+    text == "inf" || text == "infinity"
+}
+
+fn read_file(path: &Path) -> Result<String, Error> {
+    let mut contents = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut contents))
+        .map_err(|e| Error::Io { path: path.to_path_buf(), message: e.to_string() })?;
+
+    Ok(contents)
 }
 
 /// Parse the file at the given path
 pub fn parse_file<T: AsRef<Path>>(path: T) -> Result<Dict, Error> {
-    let mut f = File::open(&path).expect("file not found");
-    let mut contents = String::new();
-    // TODO: error handling
-    f.read_to_string(&mut contents).expect("something went wrong reading the file");
+    let path = path.as_ref();
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| Error::Io { path: path.to_path_buf(), message: e.to_string() })?;
+    let contents = read_file(path)?;
 
-    let state = ParserState { path: Some(&path.as_ref()) };
+    let state = ParserState { path: Some(path), stack: vec![canonical], ..Default::default() };
 
-    state.parse_str(&contents)
+    state.parse_root(&contents)
 }
 
-/// Parse the file at the given path
+/// Parse the given string
 pub fn parse_str(input: &str) -> Result<Dict, Error> {
-    let state = ParserState { path: None };
+    let state = ParserState::default();
+
+    state.parse_root(input)
+}
 
-    state.parse_str(&input)
+/// Parse the file at the given path, including all the files it includes,
+/// and returns every problem found instead of stopping at the first one
+pub fn check_file<T: AsRef<Path>>(path: T) -> Vec<Diagnostic> {
+    let path = path.as_ref();
+    let state = ParserState {
+        path: Some(path),
+        stack: path.canonicalize().into_iter().collect(),
+        diagnostics: Some(RefCell::new(vec![])),
+        ..Default::default()
+    };
+
+    let res = read_file(path).and_then(|contents| state.parse_root(&contents));
+    collect_diagnostics(state, res)
+}
+
+/// Parse the given string and returns every problem found instead of stopping
+/// at the first one
+pub fn check_str(input: &str) -> Vec<Diagnostic> {
+    let state = ParserState { diagnostics: Some(RefCell::new(vec![])), ..Default::default() };
+
+    let res = state.parse_root(input);
+    collect_diagnostics(state, res)
+}
+
+fn collect_diagnostics(state: ParserState, res: Result<Dict, Error>) -> Vec<Diagnostic> {
+    // Collecting never returns an error for things it can recover from
+    let mut diagnostics = state.diagnostics.map(|d| d.into_inner()).unwrap_or_default();
+    if let Err(error) = res {
+        diagnostics.push(Diagnostic { severity: Severity::Error, error });
+    }
+    diagnostics
 }
@@ -0,0 +1,353 @@
+//! Reaching into a parsed document with paths like `servers.alpha.ip` or
+//! `clients.data[0][1]`: dots separate the keys of dicts and brackets hold
+//! the indices of arrays.
+//! Selectors can also have wildcards to match several values: `servers.*.ip`,
+//! `clients.hosts[*]` or `..port` for the `port` keys at any depth.
+use std::fmt::Write;
+use std::mem;
+
+use errors::PathError;
+use value::{Dict, Value};
+
+
+/// A step of a path
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// A step of a selector, which can match several values
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Selector {
+    Segment(Segment),
+    /// `*`: every value of a dict
+    AnyKey,
+    /// `[*]`: every item of an array
+    AnyIndex,
+    /// `..key`: the values of that key in the dicts at any depth
+    Descendant(String),
+}
+
+/// Splits a selector into its steps
+pub(crate) fn parse_selector(selector: &str) -> Result<Vec<Selector>, PathError> {
+    let error = |message: &str| PathError { path: selector.to_string(), message: message.to_string() };
+    let mut selectors = Vec::new();
+    let mut rest = selector;
+    let mut descendant = false;
+    if let Some(r) = rest.strip_prefix("..") {
+        rest = r;
+        descendant = true;
+    }
+
+    loop {
+        if let Some(r) = rest.strip_prefix('*') {
+            if descendant {
+                return Err(error("expected a key after `..`"));
+            }
+            selectors.push(Selector::AnyKey);
+            rest = r;
+        } else {
+            let key_length = rest.find(|c| !is_key_char(c)).unwrap_or(rest.len());
+            if key_length == 0 {
+                return Err(error("expected a key"));
+            }
+            let key = rest[..key_length].to_string();
+            selectors.push(if descendant { Selector::Descendant(key) } else { Selector::Segment(Segment::Key(key)) });
+            rest = &rest[key_length..];
+        }
+
+        while let Some(inside) = rest.strip_prefix('[') {
+            let end = inside.find(']').ok_or_else(|| error("unclosed `[`"))?;
+            let index = &inside[..end];
+            if index == "*" {
+                selectors.push(Selector::AnyIndex);
+            } else if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(error("array indices must be positive integers"));
+            } else {
+                let index = index.parse().map_err(|_| error("array index is too large"))?;
+                selectors.push(Selector::Segment(Segment::Index(index)));
+            }
+            rest = &inside[end + 1..];
+        }
+
+        if let Some(r) = rest.strip_prefix("..") {
+            rest = r;
+            descendant = true;
+        } else if let Some(r) = rest.strip_prefix('.') {
+            rest = r;
+            descendant = false;
+        } else if rest.is_empty() {
+            return Ok(selectors);
+        } else {
+            return Err(error("expected a `.` or a `[` after a key"));
+        }
+    }
+}
+
+/// Splits a path into its segments, the first one always being a key
+pub(crate) fn parse_path(path: &str) -> Result<Vec<Segment>, PathError> {
+    parse_selector(path)?
+        .into_iter()
+        .map(|selector| match selector {
+            Selector::Segment(segment) => Ok(segment),
+            _ => Err(PathError {
+                path: path.to_string(),
+                message: "`*` and `..` can only be used in selectors".to_string(),
+            }),
+        })
+        .collect()
+}
+
+/// Writes segments back as a path
+pub(crate) fn format_path(segments: &[Segment]) -> String {
+    let mut path = String::new();
+    for segment in segments {
+        match *segment {
+            Segment::Key(ref k) if path.is_empty() => path.push_str(k),
+            Segment::Key(ref k) => write!(path, ".{}", k).unwrap(),
+            Segment::Index(i) => write!(path, "[{}]", i).unwrap(),
+        }
+    }
+    path
+}
+
+fn step<'a>(value: &'a Value, segment: &Segment) -> Option<&'a Value> {
+    match (value, segment) {
+        (Value::Dict(d), Segment::Key(k)) => d.get(k),
+        (Value::Array(a), Segment::Index(i)) => a.get(*i),
+        _ => None,
+    }
+}
+
+fn step_mut<'a>(value: &'a mut Value, segment: &Segment) -> Option<&'a mut Value> {
+    match (value, segment) {
+        (Value::Dict(d), Segment::Key(k)) => d.get_mut(k),
+        (Value::Array(a), Segment::Index(i)) => a.get_mut(*i),
+        _ => None,
+    }
+}
+
+/// Returns the value at the given segments, the first one having to be a key
+pub(crate) fn get_in<'a>(dict: &'a Dict, segments: &[Segment]) -> Option<&'a Value> {
+    match segments.split_first()? {
+        (Segment::Key(first), rest) => rest.iter().try_fold(dict.get(first)?, step),
+        _ => None,
+    }
+}
+
+/// Same as `get_in` but gives a mutable reference to the value
+pub(crate) fn get_in_mut<'a>(dict: &'a mut Dict, segments: &[Segment]) -> Option<&'a mut Value> {
+    match segments.split_first()? {
+        (Segment::Key(first), rest) => rest.iter().try_fold(dict.get_mut(first)?, step_mut),
+        _ => None,
+    }
+}
+
+/// Returns the value at the given path, `None` if there is nothing there or the
+/// path is invalid.
+///
+/// ```
+/// # use scl::{get, parse_str, Value};
+/// let config = parse_str("servers = { alpha = { ports = [80, 443] } }").unwrap();
+/// assert_eq!(get(&config, "servers.alpha.ports[1]"), Some(&Value::Integer(443)));
+/// assert_eq!(get(&config, "servers.beta"), None);
+/// ```
+pub fn get<'a>(dict: &'a Dict, path: &str) -> Option<&'a Value> {
+    get_in(dict, &parse_path(path).ok()?)
+}
+
+/// Same as `get` but gives a mutable reference to the value
+pub fn get_mut<'a>(dict: &'a mut Dict, path: &str) -> Option<&'a mut Value> {
+    get_in_mut(dict, &parse_path(path).ok()?)
+}
+
+/// Sets the value at the given path, returning the value it replaces if there was one.
+///
+/// Missing dicts along the path are created but arrays must already have an item at
+/// the given indices. It fails without changing anything if the path is invalid, goes
+/// through a value that is neither a dict nor an array or uses an index past the end
+/// of an array.
+///
+/// ```
+/// # use scl::{get, set, Dict, Value};
+/// let mut config = Dict::new();
+/// set(&mut config, "servers.alpha.ip", Value::String("10.0.0.1".to_string())).unwrap();
+/// assert_eq!(get(&config, "servers.alpha.ip"), Some(&Value::String("10.0.0.1".to_string())));
+/// ```
+pub fn set(dict: &mut Dict, path: &str, value: Value) -> Result<Option<Value>, PathError> {
+    let segments = parse_path(path)?;
+    let error = |(depth, message): (usize, String)| PathError {
+        path: path.to_string(),
+        message: format!("`{}` {}", format_path(&segments[..depth]), message),
+    };
+
+    // Only dicts can be created: check we won't need an array after a missing key
+    // before creating anything
+    let (first, rest) = match segments.split_first() {
+        Some((Segment::Key(k), rest)) => (k, rest),
+        _ => unreachable!("Paths always start with a key"),
+    };
+    let mut current = dict.get(first);
+    for (depth, segment) in rest.iter().enumerate() {
+        let value = match current {
+            Some(v) => v,
+            None => {
+                if rest[depth..].iter().any(|s| matches!(*s, Segment::Index(_))) {
+                    return Err(error((depth + 1, "doesn't exist and only dicts can be created".to_string())));
+                }
+                break;
+            }
+        };
+        current = match (value, segment) {
+            (Value::Dict(d), Segment::Key(k)) => d.get(k),
+            (Value::Array(a), Segment::Index(i)) if *i < a.len() => a.get(*i),
+            // The other cases make `set_in` fail before it creates anything
+            _ => break,
+        };
+    }
+
+    if rest.is_empty() {
+        return Ok(dict.insert(first.clone(), value));
+    }
+    let next = dict.entry(first.clone()).or_insert_with(|| Value::Dict(Dict::new()));
+    set_in(next, &segments, 1, value).map_err(error)
+}
+
+/// Sets the value at `segments[depth..]` in `current`, giving the depth at which it
+/// failed with the reason otherwise
+fn set_in(current: &mut Value, segments: &[Segment], depth: usize, value: Value) -> Result<Option<Value>, (usize, String)> {
+    let last = depth + 1 == segments.len();
+    match (current, &segments[depth]) {
+        (Value::Dict(d), Segment::Key(k)) => {
+            if last {
+                return Ok(d.insert(k.clone(), value));
+            }
+            let next = d.entry(k.clone()).or_insert_with(|| Value::Dict(Dict::new()));
+            set_in(next, segments, depth + 1, value)
+        }
+        (Value::Array(a), Segment::Index(i)) => {
+            let length = a.len();
+            match a.get_mut(*i) {
+                Some(v) if last => Ok(Some(mem::replace(v, value))),
+                Some(v) => set_in(v, segments, depth + 1, value),
+                None => Err((depth, format!("only has {} items", length))),
+            }
+        }
+        (v, segment) => Err((depth, mismatch(v, segment))),
+    }
+}
+
+/// Why a segment can't be applied to a value
+fn mismatch(value: &Value, segment: &Segment) -> String {
+    match *segment {
+        Segment::Key(_) => format!("is {} and not a dict", value.type_str()),
+        Segment::Index(_) => format!("is {} and not an array", value.type_str()),
+    }
+}
+
+/// Removes the value at the given path and returns it, `None` if there was
+/// nothing there or the path is invalid.
+/// Removing an item of an array shifts the items after it.
+pub fn remove(dict: &mut Dict, path: &str) -> Option<Value> {
+    let mut segments = parse_path(path).ok()?;
+    let last = segments.pop().unwrap();
+    if segments.is_empty() {
+        return match last {
+            Segment::Key(k) => remove_key(dict, &k),
+            Segment::Index(_) => unreachable!("Paths always start with a key"),
+        };
+    }
+
+    match (get_mut(dict, &format_path(&segments))?, last) {
+        (Value::Dict(d), Segment::Key(k)) => remove_key(d, &k),
+        (Value::Array(a), Segment::Index(i)) if i < a.len() => Some(a.remove(i)),
+        _ => None,
+    }
+}
+
+/// Removes a key, keeping the other ones in the same order
+#[cfg(not(feature = "preserve_order"))]
+fn remove_key(dict: &mut Dict, key: &str) -> Option<Value> {
+    dict.remove(key)
+}
+
+#[cfg(feature = "preserve_order")]
+fn remove_key(dict: &mut Dict, key: &str) -> Option<Value> {
+    dict.shift_remove(key)
+}
+
+/// Where a selector is being applied: the document or a value in it
+#[derive(Clone, Copy)]
+enum Node<'a> {
+    Document(&'a Dict),
+    Value(&'a Value),
+}
+
+impl<'a> Node<'a> {
+    /// The values of a dict or the items of an array, with the segment leading to them
+    fn children(self) -> Vec<(Segment, &'a Value)> {
+        match self {
+            Node::Document(d) | Node::Value(Value::Dict(d)) => {
+                d.iter().map(|(k, v)| (Segment::Key(k.clone()), v)).collect()
+            }
+            Node::Value(Value::Array(a)) => a.iter().enumerate().map(|(i, v)| (Segment::Index(i), v)).collect(),
+            Node::Value(_) => vec![],
+        }
+    }
+}
+
+fn select_in<'a>(node: Node<'a>, selectors: &[Selector], path: &mut Vec<Segment>, found: &mut Vec<(String, &'a Value)>) {
+    let (selector, rest) = match selectors.split_first() {
+        Some(s) => s,
+        None => {
+            if let Node::Value(v) = node {
+                found.push((format_path(path), v));
+            }
+            return;
+        }
+    };
+
+    for (segment, child) in node.children() {
+        let matches = match (selector, &segment) {
+            (Selector::Segment(s), segment) => s == segment,
+            (Selector::AnyKey, Segment::Key(_)) | (Selector::AnyIndex, Segment::Index(_)) => true,
+            (Selector::Descendant(key), Segment::Key(k)) => key == k,
+            _ => false,
+        };
+
+        path.push(segment);
+        if matches {
+            select_in(Node::Value(child), rest, path, found);
+        }
+        if let Selector::Descendant(_) = *selector {
+            // Keep looking for the key deeper
+            select_in(Node::Value(child), selectors, path, found);
+        }
+        path.pop();
+    }
+}
+
+/// Returns every value matching the selector with its path, in the order of the document.
+/// It fails only if the selector is invalid.
+///
+/// ```
+/// # use scl::{parse_str, select, Value};
+/// let config = parse_str("servers = { alpha = { port = 80 }, beta = { port = 443 } }").unwrap();
+/// let ports = select(&config, "servers.*.port").unwrap();
+/// assert_eq!(ports, vec![
+///     ("servers.alpha.port".to_string(), &Value::Integer(80)),
+///     ("servers.beta.port".to_string(), &Value::Integer(443)),
+/// ]);
+/// assert_eq!(select(&config, "..port").unwrap().len(), 2);
+/// ```
+pub fn select<'a>(dict: &'a Dict, selector: &str) -> Result<Vec<(String, &'a Value)>, PathError> {
+    let selectors = parse_selector(selector)?;
+    let mut found = Vec::new();
+    select_in(Node::Document(dict), &selectors, &mut Vec::new(), &mut found);
+    Ok(found)
+}
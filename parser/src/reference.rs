@@ -0,0 +1,269 @@
+//! References like `&database.server` point to another value of the document.
+//! They can only be resolved once the whole document is parsed and the includes
+//! merged: the parser records where each of them goes and `resolve` fills them in.
+use std::collections::{HashMap, HashSet};
+
+use errors::{Error, Location};
+use query::{self, format_path, Segment};
+use serializer;
+use value::{Dict, Value};
+
+
+/// How many references pointing to other references are followed before giving up
+const MAX_CHAIN: usize = 128;
+
+/// A `&path` in the document
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Reference {
+    pub path: Vec<Segment>,
+    pub location: Location,
+    /// The `include` statements leading to the file of the reference, innermost first
+    pub includes: Vec<Location>,
+}
+
+impl Reference {
+    fn error(&self, message: String) -> Error {
+        self.wrap(Error::Reference { path: format_path(&self.path), message, location: self.location.clone() })
+    }
+
+    /// Makes an error happening at that reference point to the includes leading to it
+    fn wrap(&self, error: Error) -> Error {
+        self.includes
+            .iter()
+            .fold(error, |cause, location| Error::Included { location: location.clone(), cause: Box::new(cause) })
+    }
+}
+
+/// A part of a string interpolating references
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Part {
+    Text(String),
+    Reference(Reference),
+}
+
+/// Adds text at the end of the parts of a string
+pub(crate) fn push_text(parts: &mut Vec<Part>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    if let Some(Part::Text(ref mut last)) = parts.last_mut() {
+        last.push_str(text);
+        return;
+    }
+    parts.push(Part::Text(text.to_string()));
+}
+
+/// What a value waiting for references to be resolved will be
+#[derive(Debug, PartialEq)]
+pub(crate) enum Deferred {
+    /// The referenced value itself
+    Reference(Reference),
+    /// A string interpolating at least one reference
+    String(Vec<Part>),
+}
+
+/// A value to fill in once the document is parsed
+#[derive(Debug, PartialEq)]
+pub(crate) struct Pending {
+    /// Where the value goes, from the root of the document
+    pub position: Vec<Segment>,
+    pub value: Deferred,
+}
+
+impl Pending {
+    /// Records that the reference comes from a file included at `location`
+    pub fn included_from(mut self, location: &Location) -> Pending {
+        match self.value {
+            Deferred::Reference(ref mut r) => r.includes.push(location.clone()),
+            Deferred::String(ref mut parts) => {
+                for part in parts {
+                    if let Part::Reference(ref mut r) = *part {
+                        r.includes.push(location.clone());
+                    }
+                }
+            }
+        }
+        self
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum State {
+    Unresolved,
+    Resolving,
+    Resolved,
+    Failed,
+}
+
+struct Resolver<'a> {
+    pending: &'a [Pending],
+    /// The index of the pending value at each position
+    at: HashMap<&'a [Segment], usize>,
+    /// The indices of the pending values at or under each position
+    under: HashMap<&'a [Segment], Vec<usize>>,
+    states: Vec<State>,
+    /// The references being followed with the index of their pending value, to describe cycles
+    stack: Vec<(usize, &'a Reference)>,
+    errors: Vec<Error>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Resolves the pending value at `index` and puts it in the document, returning
+    /// whether it worked. Errors are only recorded once, values depending on a value
+    /// that failed just fail too.
+    fn resolve(&mut self, root: &mut Dict, index: usize) -> bool {
+        match self.states[index] {
+            State::Resolved => return true,
+            State::Failed => return false,
+            State::Unresolved => (),
+            State::Resolving => unreachable!("Cycles are caught before resolving a value again"),
+        }
+        self.states[index] = State::Resolving;
+
+        let pending: &'a Pending = &self.pending[index];
+        let value = match pending.value {
+            Deferred::Reference(ref r) => self.lookup(root, index, r),
+            Deferred::String(ref parts) => {
+                let mut out = String::new();
+                let mut result = Ok(());
+                for part in parts {
+                    match *part {
+                        Part::Text(ref text) => out.push_str(text),
+                        Part::Reference(ref r) => match self.lookup(root, index, r) {
+                            Ok(Value::String(s)) => out.push_str(&s),
+                            Ok(ref v @ Value::Array(_)) | Ok(ref v @ Value::Dict(_)) => {
+                                result = Err(Some(r.error(format!(
+                                    "points to {}, which can't be interpolated in a string",
+                                    if v.is_dict() { "a dict" } else { "an array" }
+                                ))));
+                                break;
+                            }
+                            Ok(v) => out.push_str(&serializer::value_to_string(&v)),
+                            Err(e) => {
+                                result = Err(e);
+                                break;
+                            }
+                        },
+                    }
+                }
+                result.map(|_| Value::String(out))
+            }
+        };
+
+        match value {
+            Ok(value) => {
+                // The position holds a placeholder since the parser recorded it
+                if let Some(slot) = query::get_in_mut(root, &pending.position) {
+                    *slot = value;
+                }
+                self.states[index] = State::Resolved;
+                true
+            }
+            Err(error) => {
+                self.errors.extend(error);
+                self.states[index] = State::Failed;
+                false
+            }
+        }
+    }
+
+    /// Gives the value a reference points to, once every pending value around it
+    /// is resolved. The error is `None` if it was already reported.
+    fn lookup(&mut self, root: &mut Dict, index: usize, reference: &'a Reference) -> Result<Value, Option<Error>> {
+        // The values containing the target have to be resolved to reach it, and the
+        // values in it to copy it entirely
+        let path = &reference.path[..];
+        let mut dependencies: Vec<usize> = (0..path.len()).filter_map(|end| self.at.get(&path[..end]).cloned()).collect();
+        dependencies.extend(self.under.get(path).into_iter().flatten());
+
+        if self.stack.len() == MAX_CHAIN {
+            return Err(Some(reference.error(format!("can only go through {} references in a row", MAX_CHAIN))));
+        }
+        self.stack.push((index, reference));
+        for dependency in dependencies {
+            if self.states[dependency] == State::Resolving {
+                // The start of the cycle is on the stack as it's being resolved
+                let start = self.stack.iter().position(|&(i, _)| i == dependency).unwrap();
+                let mut cycle = vec![format!("`{}`", format_path(&self.pending[dependency].position))];
+                cycle.extend(self.stack[start..].iter().map(|&(_, r)| format!("`{}`", format_path(&r.path))));
+                self.stack.pop();
+                return Err(Some(reference.error(format!("is part of a cycle: {}", cycle.join(" -> ")))));
+            }
+            if !self.resolve(root, dependency) {
+                self.stack.pop();
+                return Err(None);
+            }
+        }
+        self.stack.pop();
+
+        query::get_in(root, &reference.path)
+            .cloned()
+            .ok_or_else(|| Some(reference.error("doesn't point to any value".to_string())))
+    }
+
+    /// Checks that the references put in arrays have the same type as the other items
+    fn check_arrays(&mut self, root: &Dict) {
+        let pending = self.pending;
+        let array_reference = |p: &'a Pending| match (&p.value, p.position.split_last()) {
+            (Deferred::Reference(r), Some((Segment::Index(i), parent))) => Some((r, *i, parent)),
+            _ => None,
+        };
+
+        // The indices of the items that are references, by array
+        let mut references: HashMap<&[Segment], HashSet<usize>> = HashMap::new();
+        for (_, item, parent) in pending.iter().filter_map(array_reference) {
+            references.entry(parent).or_default().insert(item);
+        }
+
+        // Each reference is compared with the first item written in its array, if there is one
+        let mut firsts: HashMap<&[Segment], usize> = HashMap::new();
+        for (index, p) in pending.iter().enumerate() {
+            let (reference, item, parent) = match array_reference(p) {
+                Some(r) if self.states[index] == State::Resolved => r,
+                _ => continue,
+            };
+            let items = match query::get_in(root, parent) {
+                Some(Value::Array(items)) => items,
+                _ => continue,
+            };
+            let first = *firsts
+                .entry(parent)
+                .or_insert_with(|| (0..items.len()).find(|i| !references[parent].contains(i)).unwrap_or(0));
+            if !items[item].same_type(&items[first]) {
+                self.errors.push(reference.wrap(Error::TypeMismatch {
+                    expected: items[first].type_str(),
+                    found: items[item].type_str(),
+                    location: reference.location.clone(),
+                    origin: None,
+                }));
+            }
+        }
+    }
+}
+
+/// Replaces the placeholders of the pending values by the values they refer to,
+/// giving back every error found in the order of the document
+pub(crate) fn resolve(root: &mut Dict, pending: &[Pending]) -> Vec<Error> {
+    let mut at = HashMap::new();
+    let mut under: HashMap<&[Segment], Vec<usize>> = HashMap::new();
+    for (index, p) in pending.iter().enumerate() {
+        at.insert(&p.position[..], index);
+        for end in 1..=p.position.len() {
+            under.entry(&p.position[..end]).or_default().push(index);
+        }
+    }
+
+    let mut resolver = Resolver {
+        pending,
+        at,
+        under,
+        states: vec![State::Unresolved; pending.len()],
+        stack: vec![],
+        errors: vec![],
+    };
+    for index in 0..pending.len() {
+        resolver.resolve(root, index);
+    }
+    resolver.check_arrays(root);
+    resolver.errors
+}
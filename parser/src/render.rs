@@ -0,0 +1,229 @@
+use std::fs;
+
+use errors::{Diagnostic, Error, Location, Severity};
+
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+
+/// Something to underline in the source
+struct Label<'e> {
+    location: &'e Location,
+    message: String,
+    primary: bool,
+}
+
+/// Renders errors and diagnostics for the humans editing the documents: the offending
+/// lines are shown with what is wrong underlined.
+///
+/// ```text
+/// error: mismatched types
+///  --> ports.scl:1:29
+///   |
+/// 1 | port = ${PORT as integer || false}
+///   |                  ------- expected because of this
+///   |                             ^^^^^ expected integer, found bool
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Renderer {
+    colored: bool,
+}
+
+impl Renderer {
+    pub fn new() -> Renderer {
+        Renderer::default()
+    }
+
+    /// Whether to colour the output with ANSI escape codes, off by default
+    pub fn colored(mut self, colored: bool) -> Renderer {
+        self.colored = colored;
+        self
+    }
+
+    /// Renders an error.
+    /// `source` is the string given to `parse_str`: the snippets of files are read from disk.
+    pub fn render(&self, error: &Error, source: Option<&str>) -> String {
+        self.render_with_severity(error, Severity::Error, source)
+    }
+
+    /// Renders a diagnostic returned by `check_file` or `check_str`.
+    /// `source` is the string given to `check_str`: the snippets of files are read from disk.
+    pub fn render_diagnostic(&self, diagnostic: &Diagnostic, source: Option<&str>) -> String {
+        self.render_with_severity(&diagnostic.error, diagnostic.severity, source)
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.colored {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn render_with_severity(&self, error: &Error, severity: Severity, source: Option<&str>) -> String {
+        let root = error.root_cause();
+        let (severity_name, color) = match severity {
+            Severity::Error => ("error", RED),
+            Severity::Warning => ("warning", YELLOW),
+        };
+
+        let mut out = format!(
+            "{}{}\n",
+            self.paint(color, &format!("{}:", severity_name)),
+            self.paint(BOLD, &format!(" {}", title(root))),
+        );
+
+        let mut labels = labels(root);
+        labels.sort_by_key(|l| (l.location.line, l.location.column));
+        let width = labels.iter().map(|l| l.location.line.to_string().len()).max().unwrap_or(0);
+        let gutter = " ".repeat(width);
+
+        if let Some(location) = root.location() {
+            out.push_str(&format!("{}{} {}\n", gutter, self.paint(BLUE, "-->"), location));
+            let text = match location.path {
+                Some(ref p) => fs::read_to_string(p).ok(),
+                None => source.map(|s| s.to_string()),
+            };
+            match text {
+                Some(ref text) => out.push_str(&self.snippet(text, &labels, width, color)),
+                None => {
+                    // We can't show the source, only what we would have underlined
+                    for label in &labels {
+                        out.push_str(&format!("{} {} {}: {}\n", gutter, self.paint(BLUE, "="), label.location, label.message));
+                    }
+                }
+            }
+        }
+
+        if let Some(help) = help(root) {
+            out.push_str(&format!("{} {} {}\n", gutter, self.paint(BLUE, "="), self.paint(CYAN, &format!("help: {}", help))));
+        }
+        for location in error.include_chain() {
+            out.push_str(&format!("{} {} note: included from {}\n", gutter, self.paint(BLUE, "="), location));
+        }
+
+        out
+    }
+
+    /// Shows the lines the labels are on with the labels under them
+    fn snippet(&self, text: &str, labels: &[Label], width: usize, color: &str) -> String {
+        let gutter = self.paint(BLUE, &format!("{} |", " ".repeat(width)));
+        let mut out = format!("{}\n", gutter);
+        let mut previous_line = None;
+
+        for label in labels {
+            let location = label.location;
+            let line = match text.lines().nth(location.line.saturating_sub(1)) {
+                Some(l) => l.trim_end_matches('\r'),
+                None => continue,
+            };
+
+            if previous_line != Some(location.line) {
+                if previous_line.is_some() {
+                    out.push_str(&format!("{}\n", gutter));
+                }
+                let number = format!("{:>width$} |", location.line, width = width);
+                out.push_str(&format!("{} {}\n", self.paint(BLUE, &number), line));
+                previous_line = Some(location.line);
+            }
+
+            // Keep the tabs before the label so it lines up with the source line
+            let padding: String = line
+                .chars()
+                .take(location.column.saturating_sub(1))
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            // We only underline the first line of spans going over several lines
+            let length = text
+                .get(location.span.0..)
+                .and_then(|rest| rest.lines().next())
+                .and_then(|rest| rest.get(..(location.span.1 - location.span.0).min(rest.len())))
+                .map_or(0, |s| s.trim_end_matches('\r').chars().count())
+                .max(1);
+            let (mark, mark_color) = if label.primary { ("^", color) } else { ("-", BLUE) };
+            out.push_str(&format!(
+                "{} {}{}\n",
+                gutter,
+                padding,
+                self.paint(mark_color, &format!("{} {}", mark.repeat(length), label.message)),
+            ));
+        }
+
+        out
+    }
+}
+
+/// A short description of the error kind
+fn title(error: &Error) -> String {
+    match *error {
+        Error::InvalidSyntax { .. } => "invalid syntax".to_string(),
+        Error::Io { ref path, ref message } => format!("failed to read {}: {}", path.display(), message),
+        Error::Include { ref path, .. } => format!("failed to include {}", path.display()),
+        Error::EnvVar { ref name, ref message, .. } => format!("environment variable `{}` {}", name, message),
+        Error::Reference { ref path, ref message, .. } => format!("reference `&{}` {}", path, message),
+        Error::TypeMismatch { .. } => "mismatched types".to_string(),
+        Error::InvalidCast { ref cast, .. } => format!("environment variable cannot be cast to {}", cast),
+        Error::InvalidValue { .. } => "invalid value".to_string(),
+        Error::OutOfRange { .. } => "number out of range".to_string(),
+        Error::DuplicateKey { ref key, .. } => format!("duplicate key `{}`", key),
+        Error::Included { ref cause, .. } => title(cause),
+    }
+}
+
+fn labels<'e>(error: &'e Error) -> Vec<Label<'e>> {
+    let primary = |location, message: String| Label { location, message, primary: true };
+    let secondary = |location, message: &str| Label { location, message: message.to_string(), primary: false };
+
+    match *error {
+        Error::Io { .. } => vec![],
+        Error::InvalidSyntax { ref message, ref location } => vec![primary(location, message.clone())],
+        Error::Include { ref message, ref location, .. } => vec![primary(location, message.clone())],
+        Error::EnvVar { ref message, ref location, .. } => vec![primary(location, message.clone())],
+        Error::Reference { ref message, ref location, .. } => vec![primary(location, message.clone())],
+        Error::TypeMismatch { expected, found, ref location, ref origin } => {
+            let mut labels = vec![primary(location, format!("expected {}, found {}", expected, found))];
+            if let Some(ref origin) = *origin {
+                labels.push(secondary(origin, "expected because of this"));
+            }
+            labels
+        }
+        Error::InvalidCast { ref value, ref cast, ref location } => {
+            vec![primary(location, format!("`{}` is not a valid {}", value, cast))]
+        }
+        Error::InvalidValue { ref message, ref location } => vec![primary(location, message.clone())],
+        Error::OutOfRange { type_name, ref location, .. } => {
+            vec![primary(location, format!("out of range for {}", type_name))]
+        }
+        Error::DuplicateKey { ref location, ref previous, .. } => {
+            vec![primary(location, "set again here".to_string()), secondary(previous, "first set here")]
+        }
+        Error::Included { ref cause, .. } => labels(cause),
+    }
+}
+
+fn help(error: &Error) -> Option<String> {
+    match *error {
+        Error::EnvVar { ref name, .. } => Some(format!(
+            "set the variable or give it a default value: `${{{} || \"default\"}}`",
+            name
+        )),
+        Error::OutOfRange { type_name: "integer", .. } => Some(format!(
+            "integers go from {} to {}",
+            i128::MIN,
+            i128::MAX
+        )),
+        Error::OutOfRange { type_name: "float", .. } => {
+            Some(format!("floats go up to {:e}, use `inf` for infinity", f64::MAX))
+        }
+        Error::OutOfRange { type_name: "byte size", .. } => {
+            Some(format!("byte sizes go up to {} bytes", u64::MAX))
+        }
+        Error::DuplicateKey { .. } => Some("remove one of them, only the last one is kept".to_string()),
+        Error::Included { ref cause, .. } => help(cause),
+        _ => None,
+    }
+}
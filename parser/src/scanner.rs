@@ -0,0 +1,185 @@
+//! A very light scanner of the structure of a document: it only knows about brackets,
+//! strings and comments.
+//! It runs outside of the grammar, to protect it or to recover from its errors, so
+//! it has to cope with invalid inputs.
+
+
+/// What the scanner is in, apart from the document itself
+#[derive(Clone, Copy, PartialEq)]
+enum Context {
+    /// A basic string closed by the given delimiter
+    String(&'static str),
+    /// An environment variable interpolated in a string
+    Interpolation,
+}
+
+/// Calls `f` with the offset of every bracket and newline that is not in a
+/// string or a comment.
+/// The `{` and `}` of environment variables interpolated in strings count as brackets
+/// too as they can have strings in them, themselves interpolating variables.
+fn walk<F: FnMut(usize, u8)>(input: &str, mut f: F) {
+    let bytes = input.as_bytes();
+    let mut contexts = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match contexts.last() {
+            Some(&Context::String(delimiter)) => match bytes[i] {
+                // skip the escaped character
+                b'\\' if delimiter == "\"" => i += 1,
+                b'"' if input[i..].starts_with(delimiter) => {
+                    contexts.pop();
+                    i += delimiter.len();
+                    continue;
+                }
+                b'$' if input[i..].starts_with("$${") => {
+                    i += 3;
+                    continue;
+                }
+                b'$' if input[i..].starts_with("${") => {
+                    contexts.push(Context::Interpolation);
+                    f(i + 1, b'{');
+                    i += 2;
+                    continue;
+                }
+                _ => (),
+            },
+            context => match bytes[i] {
+                b'#' if context.is_none() => {
+                    // stop right before the newline so it gets reported
+                    i = input[i..].find('\n').map_or(bytes.len(), |n| i + n);
+                    continue;
+                }
+                b'"' => {
+                    let delimiter = if input[i..].starts_with("\"\"\"") { "\"\"\"" } else { "\"" };
+                    contexts.push(Context::String(delimiter));
+                    i += delimiter.len();
+                    continue;
+                }
+                b'\'' if input[i..].starts_with("'''") => {
+                    i = input[i + 3..].find("'''").map_or(bytes.len(), |n| i + 3 + n + 3);
+                    continue;
+                }
+                b'\'' => {
+                    // Literal strings can't span lines: stop right before the newline of an unclosed one
+                    i = input[i + 1..].find(['\'', '\n']).map_or(bytes.len(), |n| i + 1 + n);
+                    if bytes.get(i) == Some(&b'\'') {
+                        i += 1;
+                    }
+                    continue;
+                }
+                b'}' if context.is_some() => {
+                    contexts.pop();
+                    f(i, b'}');
+                }
+                b @ b'[' | b @ b'{' | b @ b']' | b @ b'}' | b @ b'\n' if context.is_none() => f(i, b),
+                _ => (),
+            },
+        }
+        i += 1;
+    }
+}
+
+/// Returns the offset of the first bracket making arrays/dicts nested more than
+/// `max` levels deep
+pub fn find_excessive_nesting(input: &str, max: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut found = None;
+
+    walk(input, |i, b| match b {
+        b'[' | b'{' => {
+            depth += 1;
+            if depth > max && found.is_none() {
+                found = Some(i);
+            }
+        }
+        b']' | b'}' => depth = depth.saturating_sub(1),
+        _ => (),
+    });
+
+    found
+}
+
+/// A line of the document that doesn't start in a string
+struct Line {
+    start: usize,
+    end: usize,
+    /// How many brackets are open at the start of the line
+    depth: usize,
+}
+
+impl Line {
+    /// Whether there is something else than whitespace and comments on that line
+    fn has_content(&self, input: &str) -> bool {
+        let text = input[self.start..self.end].trim();
+        !text.is_empty() && !text.starts_with('#')
+    }
+}
+
+fn lines(input: &str) -> Vec<Line> {
+    let mut lines = vec![Line { start: 0, end: input.len(), depth: 0 }];
+    let mut depth: usize = 0;
+
+    walk(input, |i, b| match b {
+        b'[' | b'{' => depth += 1,
+        b']' | b'}' => depth = depth.saturating_sub(1),
+        _ => {
+            if let Some(last) = lines.last_mut() {
+                last.end = i + 1;
+            }
+            lines.push(Line { start: i + 1, end: input.len(), depth });
+        }
+    });
+
+    lines
+}
+
+/// Finds what to skip in the input to recover from a syntax error at `offset`.
+///
+/// If the error is on a line of a dict/array that doesn't open or close any brackets, that
+/// line only is skipped. Otherwise we skip the whole top-level statement the error is in.
+/// Returns `None` if there is nothing left to skip.
+pub fn recovery_range(input: &str, offset: usize) -> Option<(usize, usize)> {
+    let lines = lines(input);
+    let current = lines.iter().rposition(|l| l.start <= offset).unwrap_or(0);
+    let line = &lines[current];
+    let next_depth = lines.get(current + 1).map_or(0, |l| l.depth);
+
+    let (start, end) = if line.depth > 0 && next_depth == line.depth && line.has_content(input) {
+        (line.start, line.end)
+    } else {
+        let start = lines[..current + 1]
+            .iter()
+            .rposition(|l| l.depth == 0 && l.has_content(input))
+            .map_or(0, |i| lines[i].start);
+        let end = lines[current + 1..]
+            .iter()
+            .find(|l| l.depth == 0 && l.has_content(input))
+            .map_or(input.len(), |l| l.start);
+        (start, end)
+    };
+
+    if input[start..end].trim().is_empty() {
+        None
+    } else {
+        Some((start, end))
+    }
+}
+
+/// Replaces everything in the given range by spaces, keeping newlines so the
+/// locations in the rest of the document do not change
+pub fn blank(input: &str, (start, end): (usize, usize)) -> String {
+    let mut blanked = String::with_capacity(input.len());
+    blanked.push_str(&input[..start]);
+    for c in input[start..end].chars() {
+        if c == '\n' {
+            blanked.push('\n');
+        } else {
+            for _ in 0..c.len_utf8() {
+                blanked.push(' ');
+            }
+        }
+    }
+    blanked.push_str(&input[end..]);
+    blanked
+}
@@ -0,0 +1,235 @@
+//! Writing values back as SCL text.
+use std::fmt::Write;
+use std::time::Duration;
+
+use errors::SerializeError;
+use parser::{DURATION_UNITS, MAX_NESTING};
+use value::{DateError, Dict, Value};
+
+
+/// Writes dicts as SCL documents that `parse_str` reads back as the same dict.
+///
+/// Dicts are written on several lines, arrays on a single one unless they hold
+/// dicts or arrays or would be too wide.
+///
+/// ```
+/// # use scl::{parse_str, Serializer};
+/// let config = parse_str("db = { hosts = [\"a\", \"b\"], port = 5432 }").unwrap();
+/// let text = Serializer::new().indent(2).to_string(&config).unwrap();
+/// assert_eq!(text, "db = {\n  hosts = [\"a\", \"b\"],\n  port = 5432,\n}\n");
+/// assert_eq!(parse_str(&text).unwrap(), config);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Serializer {
+    indent: usize,
+    max_array_width: usize,
+}
+
+impl Default for Serializer {
+    fn default() -> Serializer {
+        Serializer { indent: 4, max_array_width: 80 }
+    }
+}
+
+impl Serializer {
+    pub fn new() -> Serializer {
+        Serializer::default()
+    }
+
+    /// How many spaces to indent the content of dicts and arrays with, 4 by default
+    pub fn indent(mut self, indent: usize) -> Serializer {
+        self.indent = indent;
+        self
+    }
+
+    /// How many characters an array can take on a single line, 80 by default.
+    /// Wider arrays are written with one item per line.
+    pub fn max_array_width(mut self, width: usize) -> Serializer {
+        self.max_array_width = width;
+        self
+    }
+
+    /// Writes the dict as a document.
+    /// It fails for what `parse_str` wouldn't read back: invalid keys, arrays mixing
+    /// types, dates and times that don't exist or arrays/dicts nested too deeply.
+    pub fn to_string(&self, dict: &Dict) -> Result<String, SerializeError> {
+        let mut out = String::new();
+        for (key, value) in dict {
+            let path = key_path("", key)?;
+            write!(out, "{} = ", key).unwrap();
+            self.write_value(&mut out, value, 0, &path)?;
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    fn write_value(&self, out: &mut String, value: &Value, depth: usize, path: &str) -> Result<(), SerializeError> {
+        match *value {
+            Value::String(ref s) => write_string(out, s, &" ".repeat(self.indent * (depth + 1))),
+            Value::Integer(i) => write!(out, "{}", i).unwrap(),
+            Value::WideInteger(i) => write!(out, "{}", i).unwrap(),
+            Value::Float(f) => write_float(out, f),
+            Value::Boolean(b) => write!(out, "{}", b).unwrap(),
+            Value::Date(ref d) => out.push_str(&checked(d.checked(), path)?.to_iso()),
+            Value::DateTime(ref d) => out.push_str(&checked(d.checked(), path)?.to_rfc3339()),
+            Value::Time(ref t) => out.push_str(&checked(t.checked(), path)?.to_rfc3339()),
+            Value::Duration(d) => write_duration(out, d),
+            Value::ByteSize(b) => write!(out, "{}", b).unwrap(),
+            Value::Array(_) | Value::Dict(_) if depth >= MAX_NESTING => {
+                return Err(SerializeError {
+                    path: path.to_string(),
+                    message: format!("arrays and dictionaries can only be nested {} levels deep", MAX_NESTING),
+                });
+            }
+            Value::Array(ref items) => {
+                if let Some((i, item)) = items.iter().enumerate().find(|(_, i)| !i.same_type(&items[0])) {
+                    return Err(SerializeError {
+                        path: format!("{}[{}]", path, i),
+                        message: format!("arrays can't mix types, expected {} but found {}", items[0].type_str(), item.type_str()),
+                    });
+                }
+                let nested = items.iter().any(|i| i.is_array() || i.is_dict());
+                if !nested {
+                    let mut inline = String::from("[");
+                    for (i, item) in items.iter().enumerate() {
+                        if i > 0 {
+                            inline.push_str(", ");
+                        }
+                        self.write_value(&mut inline, item, depth, &format!("{}[{}]", path, i))?;
+                    }
+                    inline.push(']');
+                    if inline.chars().count() <= self.max_array_width {
+                        out.push_str(&inline);
+                        return Ok(());
+                    }
+                }
+
+                out.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    out.push_str(&" ".repeat(self.indent * (depth + 1)));
+                    self.write_value(out, item, depth + 1, &format!("{}[{}]", path, i))?;
+                    out.push_str(",\n");
+                }
+                out.push_str(&" ".repeat(self.indent * depth));
+                out.push(']');
+            }
+            Value::Dict(ref dict) => {
+                if dict.is_empty() {
+                    out.push_str("{}");
+                    return Ok(());
+                }
+
+                out.push_str("{\n");
+                for (key, value) in dict {
+                    let path = key_path(path, key)?;
+                    write!(out, "{}{} = ", " ".repeat(self.indent * (depth + 1)), key).unwrap();
+                    self.write_value(out, value, depth + 1, &path)?;
+                    out.push_str(",\n");
+                }
+                out.push_str(&" ".repeat(self.indent * depth));
+                out.push('}');
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes the dict as a document with the default settings of `Serializer`
+pub fn to_string(dict: &Dict) -> Result<String, SerializeError> {
+    Serializer::new().to_string(dict)
+}
+
+/// Writes a value that isn't a dict as SCL, like `1.5MiB` or `"a string"`
+pub(crate) fn value_to_string(value: &Value) -> String {
+    let mut out = String::new();
+    // Only the values built by hand can fail to be written, not the ones of a parsed document
+    Serializer::new().write_value(&mut out, value, 0, "").unwrap();
+    out
+}
+
+/// Turns a date or time that doesn't exist into an error for the value at `path`
+fn checked<T>(result: Result<T, DateError>, path: &str) -> Result<T, SerializeError> {
+    result.map_err(|e| SerializeError { path: path.to_string(), message: e.to_string() })
+}
+
+/// Checks the key can be written in SCL and returns the path to its value
+fn key_path(parent: &str, key: &str) -> Result<String, SerializeError> {
+    let path = if parent.is_empty() { key.to_string() } else { format!("{}.{}", parent, key) };
+    let mut chars = key.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+    if valid {
+        Ok(path)
+    } else {
+        Err(SerializeError { path, message: format!("`{}` is not a valid key", key) })
+    }
+}
+
+/// Writes strings ending with a newline as multiline strings indented by `indentation`.
+/// `${` is escaped as `$${` in basic strings so it isn't read as an environment variable.
+fn write_string(out: &mut String, s: &str, indentation: &str) {
+    // Multiline strings can't hold `"""` and turn a backslash at the end of a line into a continuation
+    let multiline = s.len() > 1
+        && s.ends_with('\n')
+        && !s.contains("\"\"\"")
+        && !s.contains("\\\n")
+        && !s.chars().any(|c| c.is_control() && c != '\n' && c != '\t');
+    if multiline {
+        out.push_str("\"\"\"\n");
+        for line in s.lines() {
+            if !line.is_empty() {
+                out.push_str(indentation);
+                out.push_str(&line.replace("${", "$${"));
+            }
+            out.push('\n');
+        }
+        write!(out, "{}\"\"\"", indentation).unwrap();
+        return;
+    }
+
+    // Literal strings are easier to read for paths and regexes
+    if s.contains('\\') && !s.contains('\'') && !s.chars().any(char::is_control) {
+        write!(out, "'{}'", s).unwrap();
+        return;
+    }
+
+    out.push('"');
+    for c in s.replace("${", "$${").chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => write!(out, "\\u{{{:X}}}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_float(out: &mut String, f: f64) {
+    if f.is_nan() {
+        out.push_str("nan");
+    } else {
+        // The debug representation is the shortest one reading back as the same float
+        // and always has a `.` or an exponent, unlike `1` for `1.0` with `Display`
+        write!(out, "{:?}", f).unwrap();
+    }
+}
+
+fn write_duration(out: &mut String, duration: Duration) {
+    let mut nanoseconds = duration.as_nanos();
+    if nanoseconds == 0 {
+        out.push_str("0s");
+        return;
+    }
+    for &(unit, size) in &DURATION_UNITS {
+        if nanoseconds >= size {
+            write!(out, "{}{}", nanoseconds / size, unit).unwrap();
+            nanoseconds %= size;
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::convert::TryFrom;
+
+use errors::{ConversionError, TypeError};
+use value::{Date, DateTime, Time, Value};
+
+#[cfg(feature = "chrono")]
+#[test]
+fn converts_dates_to_and_from_chrono() {
+    use chrono::NaiveDate;
+
+    let date = Date::new(2024, 2, 29).unwrap();
+    let naive = NaiveDate::try_from(date.clone()).unwrap();
+    assert_eq!(naive, NaiveDate::from_ymd_opt(2024, 2, 29).unwrap());
+    assert_eq!(Date::try_from(naive).unwrap(), date);
+    assert_eq!(NaiveDate::try_from(Value::Date(date)).unwrap(), naive);
+
+    let too_far = NaiveDate::from_ymd_opt(10000, 1, 1).unwrap();
+    assert_eq!(Date::try_from(too_far).unwrap_err().to_string(), "year 10000 is not between 1000 and 9999");
+    let negative = NaiveDate::from_ymd_opt(-1, 1, 1).unwrap();
+    assert_eq!(Date::try_from(negative).unwrap_err().to_string(), "year -1 is not between 1000 and 9999");
+    assert_eq!(
+        NaiveDate::try_from(Value::Boolean(true)).unwrap_err(),
+        ConversionError::Type(TypeError { expected: "date", found: "bool" })
+    );
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn converts_dates_to_and_from_time() {
+    let date = Date::new(1999, 12, 31).unwrap();
+    let converted = time::Date::try_from(date.clone()).unwrap();
+    assert_eq!(converted, time::Date::from_calendar_date(1999, time::Month::December, 31).unwrap());
+    assert_eq!(Date::try_from(converted).unwrap(), date);
+    assert_eq!(time::Date::try_from(Value::Date(date)).unwrap(), converted);
+
+    let negative = time::Date::from_calendar_date(-5, time::Month::March, 1).unwrap();
+    assert_eq!(Date::try_from(negative).unwrap_err().to_string(), "year -5 is not between 1000 and 9999");
+    assert_eq!(
+        time::Date::try_from(Value::Integer(1)).unwrap_err(),
+        ConversionError::Type(TypeError { expected: "date", found: "integer" })
+    );
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn converts_datetimes_to_chrono() {
+    let datetime: DateTime = "1979-05-27T07:32:00.5-08:00".parse().unwrap();
+    let converted = chrono::DateTime::<chrono::FixedOffset>::try_from(datetime.clone()).unwrap();
+    assert_eq!(converted.offset().local_minus_utc(), -8 * 3600);
+    assert_eq!(converted.timestamp_subsec_nanos(), 500_000_000);
+    assert_eq!(converted.timestamp(), datetime.unix_timestamp());
+    assert_eq!(
+        chrono::NaiveTime::try_from(Value::Time(Time::new(7, 32, 0, 0).unwrap())).unwrap(),
+        chrono::NaiveTime::from_hms_opt(7, 32, 0).unwrap()
+    );
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn converts_datetimes_to_time() {
+    let datetime: DateTime = "1979-05-27T07:32:00-08:30".parse().unwrap();
+    let converted = time::OffsetDateTime::try_from(datetime.clone()).unwrap();
+    assert_eq!(converted.unix_timestamp(), datetime.unix_timestamp());
+    assert_eq!(converted.offset().whole_minutes(), -510);
+    assert_eq!(
+        time::Time::try_from(Value::Time(Time::new(7, 32, 0, 5).unwrap())).unwrap(),
+        time::Time::from_hms_nano(7, 32, 0, 5).unwrap()
+    );
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn error_on_converting_invalid_values_to_chrono() {
+    let date = Date { year: 2023, month: 2, day: 30 };
+    assert_eq!(chrono::NaiveDate::try_from(date.clone()).unwrap_err().to_string(), "February 2023 only has 28 days");
+    assert_eq!(
+        chrono::NaiveDate::try_from(Value::Date(date)).unwrap_err().to_string(),
+        "February 2023 only has 28 days"
+    );
+    let time = Time { hour: 24, minute: 0, second: 0, nanosecond: 0 };
+    assert_eq!(chrono::NaiveTime::try_from(time.clone()).unwrap_err().to_string(), "hour 24 is out of range (00 to 23)");
+    let datetime = DateTime { date: Date::new(2023, 2, 1).unwrap(), time: Time::new(1, 0, 0, 0).unwrap(), offset: 1440 };
+    assert_eq!(
+        chrono::DateTime::<chrono::FixedOffset>::try_from(datetime).unwrap_err().to_string(),
+        "offset of 1440 minutes is more than a day"
+    );
+}
+
+#[cfg(feature = "time")]
+#[test]
+fn error_on_converting_invalid_values_to_time() {
+    let date = Date { year: 2023, month: 13, day: 1 };
+    assert_eq!(time::Date::try_from(date).unwrap_err().to_string(), "there is no month 13");
+    let t = Time { hour: 1, minute: 0, second: 0, nanosecond: 1_000_000_000 };
+    assert_eq!(
+        time::Time::try_from(Value::Time(t)).unwrap_err().to_string(),
+        "1000000000 nanoseconds is more than a second"
+    );
+    let datetime = DateTime { date: Date::new(2023, 2, 1).unwrap(), time: Time::new(1, 0, 0, 0).unwrap(), offset: -1440 };
+    assert_eq!(
+        time::OffsetDateTime::try_from(datetime).unwrap_err().to_string(),
+        "offset of -1440 minutes is more than a day"
+    );
+}
@@ -7,6 +7,7 @@ macro_rules! assert_lex_rule {
         let res = SclParser::parse($rule, $input);
         println!("{:?}", $input);
         //println!("{:#?}", res);
+        #[allow(clippy::unnecessary_unwrap)]
         if res.is_err() {
             println!("{}", res.unwrap_err());
             panic!();
@@ -18,12 +19,24 @@ macro_rules! assert_lex_rule {
 
 #[test]
 fn lex_int() {
-    let inputs = vec!["-10", "0", "100", "250000", "1_000", "2_500_000"];
+    let inputs = vec![
+        "-10", "+10", "0", "100", "250000", "1_000", "2_500_000",
+        "0xFF00", "0xdead_beef", "-0x1", "0o644", "0o1_777", "0b1010", "0b1111_0000", "+0b1",
+    ];
     for i in inputs {
         assert_lex_rule!(Rule::int, i);
     }
 }
 
+#[test]
+fn lex_invalid_int() {
+    let inputs = vec!["0x", "0xFF_0", "0o8", "0o7_77", "0b2", "0b1_01", "0XFF", "+-1", "0x_FF"];
+    for i in inputs {
+        let res = SclParser::parse(Rule::int, i);
+        assert!(res.is_err() || res.unwrap().last().unwrap().into_span().end() != i.len(), "{} should not lex", i);
+    }
+}
+
 #[test]
 fn lex_comment() {
     let inputs = vec!["# hey", "# hey #", "#hey \n", "# hey //"];
@@ -35,7 +48,8 @@ fn lex_comment() {
 #[test]
 fn lex_float() {
     let inputs = vec![
-        "0.0", "2.0", "123.5", "123.5", "0.1", "-1.1", "1_000.1", "1_000.1"
+        "0.0", "2.0", "123.5", "123.5", "0.1", "-1.1", "1_000.1", "1_000.1",
+        "6.02e23", "1e-9", "1E+10", "-2.5E3", "+1.0", "inf", "+inf", "-inf", "nan", "-nan",
     ];
     for i in inputs {
         assert_lex_rule!(Rule::float, i);
@@ -44,7 +58,7 @@ fn lex_float() {
 
 #[test]
 fn lex_byte_size() {
-    let inputs = vec!["2MB", "10MB", "100_000GB", "123.5kB", "123.5KB"];
+    let inputs = vec!["2MB", "10MB", "100_000GB", "123.5kB", "123.5KB", "64KiB", "1.5GiB", "2PiB"];
     for i in inputs {
         assert_lex_rule!(Rule::byte_size, i);
     }
@@ -52,12 +66,29 @@ fn lex_byte_size() {
 
 #[test]
 fn lex_string() {
-    let inputs = vec!["\"Blabla\"", "\"123\""];
+    let inputs = vec!["\"Blabla\"", "\"123\"", r#""say \"hi\"""#, r#""\\""#, r#""\q""#, r#""a ${B} $${C}""#, r#""${ B || "c" }""#];
     for i in inputs {
         assert_lex_rule!(Rule::string, i);
     }
 }
 
+#[test]
+fn lex_literal_string() {
+    let inputs = vec!["''", r"'C:\Users\me'", r#"'say "hi"\'"#];
+    for i in inputs {
+        assert_lex_rule!(Rule::literal_string, i);
+    }
+    assert!(SclParser::parse(Rule::literal_string, "'two\nlines'").is_err());
+}
+
+#[test]
+fn lex_multiline_literal_string() {
+    let inputs = vec!["''''''", "'''a 'quoted' \"\"\" \\n\nstring'''"];
+    for i in inputs {
+        assert_lex_rule!(Rule::multiline_literal_string, i);
+    }
+}
+
 #[test]
 fn lex_multiline_string() {
     let inputs = vec![r#""""Blabla""""#, r#""""a \n\r "'string""""#];
@@ -74,6 +105,35 @@ fn lex_date() {
     }
 }
 
+#[test]
+fn lex_time() {
+    let inputs = vec!["00:00:00", "07:32:00", "23:59:59.999999"];
+    for i in inputs {
+        assert_lex_rule!(Rule::time, i);
+    }
+}
+
+#[test]
+fn lex_datetime() {
+    let inputs = vec![
+        "1979-05-27T07:32:00Z",
+        "1979-05-27t07:32:00z",
+        "1979-05-27 07:32:00.5-08:00",
+        "1979-05-27T00:32:00.999999+07:00",
+    ];
+    for i in inputs {
+        assert_lex_rule!(Rule::datetime, i);
+    }
+}
+
+#[test]
+fn lex_duration() {
+    let inputs = vec!["30s", "5m", "1h30m", "250ms", "0ns", "1d2h3m4s5ms6us7ns", "1_000ms"];
+    for i in inputs {
+        assert_lex_rule!(Rule::duration, i);
+    }
+}
+
 #[test]
 fn lex_key() {
     let inputs = vec!["hello", "hello_", "hello_1", "HELLO", "_1"];
@@ -95,6 +155,9 @@ fn lex_env_var() {
         "${HELLO as float ||1.0}",
         "${HELLO as bool || false }",
         "${HELLO as date || false }",
+        "${HELLO as datetime || 1979-05-27T07:32:00Z }",
+        "${HELLO as time || 07:32:00 }",
+        "${HELLO as duration || 1h30m }",
     ];
     for i in inputs {
         println!("{:?}", i);
@@ -102,6 +165,18 @@ fn lex_env_var() {
     }
 }
 
+#[test]
+fn lex_reference() {
+    let inputs = vec!["&a", "&database.server", "&clients.data[0][1]", "&a-b._c[10].d"];
+    for i in inputs {
+        assert_lex_rule!(Rule::reference, i);
+    }
+    for i in &["&", "&a.", "&a[]", "&a[-1]", "&1a"] {
+        let res = SclParser::parse(Rule::reference, i);
+        assert!(res.is_err() || res.unwrap().last().unwrap().into_span().end() != i.len(), "{} should not lex", i);
+    }
+}
+
 #[test]
 fn lex_value() {
     let inputs = vec![
@@ -112,6 +187,7 @@ fn lex_value() {
         "\"ho\"",
         "2012-01-01",
         "${HELLO || \"hel lo\"}",
+        "&a.b[0]",
         "[1,2 ,3]",
         "{}",
         "{ hey = 1}",
@@ -1,2 +1,8 @@
 mod lexer;
 mod parser;
+mod query;
+mod render;
+mod serializer;
+mod value;
+#[cfg(any(feature = "chrono", feature = "time"))]
+mod interop;
@@ -1,11 +1,13 @@
 use std::env;
 use std::io::prelude::*;
 use std::fs::File;
+use std::time::Duration;
 
 use tempdir::TempDir;
 
-use ::parser::parse_str;
-use value::{Date, Dict, Value};
+use ::parser::{check_str, parse_file, parse_str};
+use errors::{Error, Severity};
+use value::{ByteSize, ByteUnit, Date, DateTime, Dict, Time, Value};
 
 #[test]
 fn parse_empty_document() {
@@ -27,8 +29,10 @@ fn parse_simple_key_value() {
 
     let inputs = vec![
         ("val = 2", Value::Integer(2)),
-        ("val = 2", Value::Integer(2)),
-        ("val = 1.5GB", Value::Integer(1.5e9 as i64)),
+        ("val = 1_000_000", Value::Integer(1_000_000)),
+        ("val = 100_000GB", Value::ByteSize(ByteSize::new(100_000_000_000_000, ByteUnit::Gigabyte))),
+        ("val = 1_000.5", Value::Float(1_000.5)),
+        ("val = 1.5GB", Value::ByteSize(ByteSize::new(1_500_000_000, ByteUnit::Gigabyte))),
         ("val = 2.0", Value::Float(2.0)),
         ("val = true", Value::Boolean(true)),
         ("val = false", Value::Boolean(false)),
@@ -80,7 +84,68 @@ fn parse_multiline_followed_by_newline() {
     "#,
     ).unwrap();
     assert_eq!(doc.len(), 1);
-    assert_eq!(doc["val"], Value::String("hey".to_string()));
+    // Only the newline is dropped: the closing delimiter isn't on its own line so nothing is dedented
+    assert_eq!(doc["val"], Value::String("        hey".to_string()));
+}
+
+#[test]
+fn parse_indented_multiline_strings() {
+    let inputs = vec![
+        (
+            "db = {\n    query = \"\"\"\n        SELECT *\n          FROM users\n\n        WHERE id = 1\n        \"\"\",\n}",
+            "SELECT *\n  FROM users\n\nWHERE id = 1\n",
+        ),
+        // Lines of whitespace shorter than the indentation are empty
+        ("val = \"\"\"\n    a\n  \n    b\n    \"\"\"", "a\n\nb\n"),
+        ("val = '''\n\t\tC:\\Users\\\n\t\t'''", "C:\\Users\\\n"),
+        ("val = \"\"\"\r\n  a\r\n  \"\"\"", "a\r\n"),
+        ("val = \"\"\"\n  \"\"\"", ""),
+        // Only a single newline is dropped, and the closing delimiter has to be alone on its line
+        ("val = \"\"\"\n\n  a\n  b\"\"\"", "\n  a\n  b"),
+        ("val = \"\"\"  a\n  \"\"\"", "  a\n"),
+    ];
+
+    for (text, expected) in inputs {
+        let doc = parse_str(text).unwrap();
+        let value = doc.get("val").or_else(|| doc["db"].as_dict().unwrap().get("query")).unwrap();
+        assert_eq!(value, &Value::String(expected.to_string()), "{}", text);
+    }
+}
+
+#[test]
+fn parse_multiline_line_continuations() {
+    let inputs = vec![
+        ("val = \"\"\"\n    The quick \\\n    brown fox\n    \"\"\"", "The quick brown fox\n"),
+        ("val = \"\"\"one \\\n\n\n      two\\\n\"\"\"", "one two"),
+        ("val = \"\"\"a \\ b\\n\"\"\"", "a \\ b\\n"),
+        // Literal strings are taken verbatim
+        ("val = '''\n    a \\\n    b\n    '''", "a \\\nb\n"),
+    ];
+
+    for (text, expected) in inputs {
+        let doc = parse_str(text).unwrap();
+        assert_eq!(doc["val"], Value::String(expected.to_string()), "{}", text);
+    }
+}
+
+#[test]
+fn error_on_less_indented_multiline_line() {
+    let input = "db = {\n    query = \"\"\"\n        SELECT *\n      FROM users\n        \"\"\",\n}";
+    match parse_str(input) {
+        Err(Error::InvalidValue { message, location }) => {
+            assert_eq!(message, "this line is less indented than the closing `\"\"\"`");
+            assert_eq!((location.line, location.column), (4, 1));
+            assert_eq!(&input[location.span.0..location.span.1], " ");
+        }
+        res => panic!("Unexpected result: {:?}", res),
+    }
+
+    match parse_str("val = '''\n\tmixed\n    '''") {
+        Err(Error::InvalidValue { message, .. }) => {
+            assert_eq!(message, "this line is less indented than the closing `'''`");
+        }
+        res => panic!("Unexpected result: {:?}", res),
+    }
 }
 
 #[test]
@@ -138,6 +203,9 @@ fn parse_non_local_include() {
     let doc = parse_str(&format!(r#"include "{}""#, file_path.display())).unwrap();
     assert_eq!(doc.len(), 1);
     assert_eq!(doc["key"], Value::Boolean(true));
+
+    let doc = parse_str(&format!("include '{}'", file_path.display())).unwrap();
+    assert_eq!(doc["key"], Value::Boolean(true));
 }
 
 #[test]
@@ -153,3 +221,796 @@ fn parse_include_on_key() {
     assert_eq!(doc.len(), 1);
     assert_eq!(doc["hey"], Value::Dict(expected));
 }
+
+#[test]
+fn parse_env_var_casts() {
+    env::set_var("SCL_CAST_FLOAT", "1.5");
+    env::set_var("SCL_CAST_BOOL", "false");
+    env::set_var("SCL_CAST_DATE", "2018-02-03");
+    env::set_var("SCL_CAST_DATETIME", "2018-02-03T10:00:00+01:00");
+    env::set_var("SCL_CAST_TIME", "10:00:00");
+    env::set_var("SCL_CAST_DURATION", "1m30s");
+
+    let inputs = vec![
+        ("val = ${SCL_CAST_FLOAT as float}", Value::Float(1.5)),
+        ("val = ${SCL_CAST_BOOL as bool || true}", Value::Boolean(false)),
+        (
+            "val = ${SCL_CAST_DATE as date}",
+            Value::Date(Date { year: 2018, month: 2, day: 3 }),
+        ),
+        (
+            "val = ${SCL_CAST_DATETIME as datetime}",
+            Value::DateTime("2018-02-03T10:00:00+01:00".parse().unwrap()),
+        ),
+        ("val = ${SCL_CAST_TIME as time}", Value::Time(Time::new(10, 0, 0, 0).unwrap())),
+        ("val = ${SCL_CAST_UNSET as time || 12:30:00}", Value::Time(Time::new(12, 30, 0, 0).unwrap())),
+        ("val = ${SCL_CAST_DURATION as duration}", Value::Duration(Duration::from_secs(90))),
+        ("val = ${SCL_CAST_UNSET || 250ms}", Value::Duration(Duration::from_millis(250))),
+    ];
+
+    for (text, val) in inputs {
+        let doc = parse_str(text).unwrap();
+        assert_eq!(doc["val"], val);
+    }
+}
+
+#[test]
+fn error_on_missing_env_var() {
+    match parse_str("val = ${SCL_DOES_NOT_EXIST}").unwrap_err() {
+        Error::EnvVar { name, location, .. } => {
+            assert_eq!(name, "SCL_DOES_NOT_EXIST");
+            assert_eq!(location.span, (6, 27));
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn error_on_invalid_env_var_cast() {
+    env::set_var("SCL_NOT_A_NUMBER", "hello");
+    env::set_var("SCL_NOT_A_DATE", "2018-13-01");
+
+    for cast in &["integer", "float", "bool"] {
+        let input = format!("val = ${{SCL_NOT_A_NUMBER as {}}}", cast);
+        match parse_str(&input).unwrap_err() {
+            Error::InvalidCast { value, cast: c, .. } => {
+                assert_eq!(value, "hello");
+                assert_eq!(c, *cast);
+            }
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    match parse_str("val = ${SCL_NOT_A_DATE as date}").unwrap_err() {
+        Error::InvalidCast { value, .. } => assert_eq!(value, "2018-13-01"),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+
+    // Too large for a float, like the `1e999` literal
+    env::set_var("SCL_HUGE_FLOAT", "1e400");
+    match parse_str("val = ${SCL_HUGE_FLOAT as float}").unwrap_err() {
+        Error::InvalidCast { value, cast, .. } => assert_eq!((value.as_str(), cast.as_str()), ("1e400", "float")),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+    env::set_var("SCL_INFINITE_FLOAT", "-inf");
+    assert_eq!(parse_str("val = ${SCL_INFINITE_FLOAT as float}").unwrap()["val"], Value::Float(f64::NEG_INFINITY));
+}
+
+#[test]
+fn error_on_env_var_cast_default_mismatch() {
+    match parse_str("val = ${SCL_PORT as integer || false}").unwrap_err() {
+        Error::TypeMismatch { expected, found, location, origin } => {
+            assert_eq!(expected, "integer");
+            assert_eq!(found, "bool");
+            assert_eq!(location.column, 32);
+            assert_eq!(origin.unwrap().column, 21);
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn error_on_mixed_array() {
+    match parse_str("val = [1, 2, \"3\"]").unwrap_err() {
+        Error::TypeMismatch { expected, found, location, origin } => {
+            assert_eq!(expected, "integer");
+            assert_eq!(found, "string");
+            assert_eq!(location.column, 14);
+            assert_eq!(origin.unwrap().column, 8);
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn parse_integer_bases_and_signs() {
+    let inputs = vec![
+        ("+42", 42),
+        ("-0", 0),
+        ("0xFF00", 0xFF00),
+        ("0xdead_beef", 0xdead_beef),
+        ("-0x10", -16),
+        ("0o644", 0o644),
+        ("0o1_777", 0o1777),
+        ("0b1010", 0b1010),
+        ("+0b1111_0000", 0b1111_0000),
+    ];
+
+    for (text, expected) in inputs {
+        let doc = parse_str(&format!("val = {}", text)).unwrap();
+        assert_eq!(doc["val"], Value::Integer(expected), "{}", text);
+    }
+
+    let doc = parse_str("val = 0xFFFF_FFFF_FFFF_FFFF").unwrap();
+    assert_eq!(doc["val"].as_u64(), Some(u64::MAX));
+    let doc = parse_str("val = +1.5").unwrap();
+    assert_eq!(doc["val"], Value::Float(1.5));
+
+    env::set_var("SCL_HEX_MASK", "0x0F");
+    let doc = parse_str("val = ${SCL_HEX_MASK as integer}").unwrap();
+    assert_eq!(doc["val"], Value::Integer(15));
+
+    for input in &["val = 0x", "val = 0o9", "val = 0b12", "val = 0xF_F"] {
+        match parse_str(input).unwrap_err() {
+            Error::InvalidSyntax { .. } => (),
+            e => panic!("Unexpected error for {}: {:?}", input, e),
+        }
+    }
+    match parse_str("val = 0x1_0000_0000_0000_0000_0000_0000_0000_0000").unwrap_err() {
+        Error::OutOfRange { type_name, .. } => assert_eq!(type_name, "integer"),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn parse_floats_with_exponents_and_special_values() {
+    let inputs = vec![
+        ("6.02e23", 6.02e23),
+        ("1e-9", 1e-9),
+        ("1E+10", 1e10),
+        ("-2.5E3", -2500.0),
+        ("1_000e3", 1e6),
+        ("inf", f64::INFINITY),
+        ("+inf", f64::INFINITY),
+        ("-inf", f64::NEG_INFINITY),
+    ];
+
+    for (text, expected) in inputs {
+        let doc = parse_str(&format!("val = {}", text)).unwrap();
+        assert_eq!(doc["val"], Value::Float(expected), "{}", text);
+    }
+
+    for text in &["nan", "-nan", "+nan"] {
+        match parse_str(&format!("val = {}", text)).unwrap()["val"] {
+            Value::Float(f) => assert!(f.is_nan()),
+            ref v => panic!("Unexpected value for {}: {:?}", text, v),
+        }
+    }
+
+    for input in &["val = 1e", "val = 1.5e+", "val = 1.e5", "val = .5e3", "val = infinity", "val = 01e5"] {
+        match parse_str(input).unwrap_err() {
+            Error::InvalidSyntax { .. } => (),
+            e => panic!("Unexpected error for {}: {:?}", input, e),
+        }
+    }
+
+    match parse_str("val = 1.5e999").unwrap_err() {
+        Error::OutOfRange { value, type_name, location } => {
+            assert_eq!(value, "1.5e999");
+            assert_eq!(type_name, "float");
+            assert_eq!(location.span, (6, 13));
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn parse_wide_integers() {
+    let doc = parse_str("a = 9_223_372_036_854_775_807\nb = 18_446_744_073_709_551_615\nc = -9_223_372_036_854_775_809").unwrap();
+    assert_eq!(doc["a"], Value::Integer(i64::MAX));
+    assert_eq!(doc["b"], Value::WideInteger(i128::from(u64::MAX)));
+    assert_eq!(doc["c"], Value::WideInteger(i128::from(i64::MIN) - 1));
+
+    assert_eq!(doc["a"].as_u64(), Some(i64::MAX as u64));
+    assert_eq!(doc["b"].as_u64(), Some(u64::MAX));
+    assert_eq!(doc["b"].as_i64(), None);
+    assert_eq!(doc["c"].as_u64(), None);
+    assert_eq!(doc["c"].as_i128(), Some(i128::from(i64::MIN) - 1));
+    assert_eq!(Value::Integer(-1).as_u64(), None);
+    assert_eq!(Value::Boolean(true).as_i128(), None);
+    assert_eq!(doc["b"].type_str(), "integer");
+
+    // Wide and normal integers can be mixed in arrays
+    assert!(parse_str("a = [1, 18_446_744_073_709_551_615]").is_ok());
+
+    env::set_var("SCL_WIDE_INTEGER", "18446744073709551615");
+    let doc = parse_str("val = ${SCL_WIDE_INTEGER as integer}").unwrap();
+    assert_eq!(doc["val"].as_u64(), Some(u64::MAX));
+}
+
+#[test]
+fn error_on_integer_overflow() {
+    let input = "val = 170_141_183_460_469_231_731_687_303_715_884_105_728";
+    match parse_str(input).unwrap_err() {
+        Error::OutOfRange { value, type_name, location } => {
+            assert_eq!(value, &input[6..]);
+            assert_eq!(type_name, "integer");
+            assert_eq!(location.span, (6, input.len()));
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn error_on_too_much_nesting() {
+    let input = format!("val = {}{}", "[".repeat(1000), "]".repeat(1000));
+    match parse_str(&input).unwrap_err() {
+        Error::InvalidSyntax { location, .. } => assert_eq!(location.column, 135),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+
+    // brackets in strings and comments don't count
+    let input = format!("val = \"{}\" # {}", "[".repeat(1000), "{".repeat(1000));
+    assert!(parse_str(&input).is_ok());
+}
+
+#[test]
+fn error_on_include_cycle() {
+    let tmp_dir = TempDir::new("tests").unwrap();
+    let file_path = tmp_dir.path().join("self.scl");
+    let mut f = File::create(&file_path).unwrap();
+    f.write_all(br#"include "self.scl""#).unwrap();
+    f.sync_all().unwrap();
+
+    match parse_file(&file_path).unwrap_err() {
+        Error::Include { path, .. } => assert_eq!(path, tmp_dir.path().join("self.scl")),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn check_recovers_from_syntax_errors() {
+    let input = r#"
+    a = 1 b = 2
+    c = [
+        1,
+        2 3,
+        4,
+    ]
+    d = { e = , f = 1 }
+    g = true
+    h = "#;
+    let lines: Vec<_> = check_str(input)
+        .into_iter()
+        .map(|d| {
+            assert_eq!(d.severity, Severity::Error);
+            d.error.location().unwrap().line
+        })
+        .collect();
+    assert_eq!(lines, vec![2, 5, 8, 10]);
+}
+
+#[test]
+fn check_valid_document() {
+    assert_eq!(check_str("a = 1\nb = [1, 2]"), vec![]);
+}
+
+#[test]
+fn parse_datetime_and_time() {
+    let doc = parse_str("start = 2024-03-01T22:00:00Z\nend = 2024-03-02 01:30:00.5+02:00\nat = 04:00:00").unwrap();
+    let start: DateTime = "2024-03-01T22:00:00Z".parse().unwrap();
+    assert_eq!(doc["start"], Value::DateTime(start));
+    match doc["end"] {
+        Value::DateTime(ref end) => {
+            assert_eq!(end.offset, 120);
+            assert_eq!(end.time.nanosecond, 500_000_000);
+        }
+        ref v => panic!("Unexpected value: {:?}", v),
+    }
+    assert_eq!(doc["at"], Value::Time(Time::new(4, 0, 0, 0).unwrap()));
+}
+
+#[test]
+fn error_on_invalid_datetime() {
+    match parse_str("val = 2023-02-29T10:00:00Z").unwrap_err() {
+        Error::InvalidValue { message, location } => {
+            assert_eq!(message, "February 2023 only has 28 days");
+            assert_eq!(location.span, (6, 26));
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+    match parse_str("val = 10:00:00.1234567891").unwrap_err() {
+        Error::InvalidValue { message, .. } => {
+            assert_eq!(message, "fractions of second can only have up to 9 digits");
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+    let inputs = vec![
+        ("t = 24:00:00", "hour 24 is out of range (00 to 23)"),
+        ("t = 23:60:00", "minute 60 is out of range (00 to 59)"),
+        ("t = 10:00:61", "second 61 is out of range (00 to 59)"),
+        ("d = 2023-02-01T10:00:00+25:00", "offset `+25:00` is out of range (-23:59 to +23:59)"),
+        ("d = 2023-02-01T10:00:00-10:60", "offset `-10:60` is out of range (-23:59 to +23:59)"),
+    ];
+    for (input, expected) in inputs {
+        match parse_str(input).unwrap_err() {
+            Error::InvalidValue { message, location } => {
+                assert_eq!(message, expected);
+                assert_eq!(location.span, (4, input.len()));
+            }
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+}
+
+#[test]
+fn parse_byte_sizes() {
+    let inputs = vec![
+        ("10kB", 10_000),
+        ("10KB", 10_000),
+        ("1.5MB", 1_500_000),
+        ("2GB", 2_000_000_000),
+        ("64KiB", 65_536),
+        ("1.5MiB", 1_572_864),
+        ("2GiB", 2_147_483_648),
+        ("1TiB", 1_099_511_627_776),
+        ("1PiB", 1_125_899_906_842_624),
+        ("1.000000001GB", 1_000_000_001),
+        ("0.5KiB", 512),
+        ("0.0009765625MiB", 1024),
+        ("1.250kB", 1250),
+        ("18_446_744_073_709.551615MB", u64::MAX),
+    ];
+
+    for (text, expected) in inputs {
+        let doc = parse_str(&format!("val = {}", text)).unwrap();
+        match doc["val"] {
+            Value::ByteSize(size) => assert_eq!(size.as_bytes(), expected, "{}", text),
+            ref v => panic!("Unexpected value for {}: {:?}", text, v),
+        }
+    }
+
+    let doc = parse_str("a = 1.5KB\nb = 0.5GiB").unwrap();
+    assert_eq!(doc["a"], Value::ByteSize(ByteSize::new(1500, ByteUnit::Kilobyte)));
+    assert_eq!(doc["b"], Value::ByteSize(ByteSize::new(1 << 29, ByteUnit::Gibibyte)));
+}
+
+#[test]
+fn parse_byte_sizes_with_long_fractions() {
+    // A single byte takes 40 digits in TiB and 50 in PiB
+    let inputs = vec![
+        ("0.0000000000009094947017729282379150390625TiB", 1),
+        ("0.00000000000000088817841970012523233890533447265625PiB", 1),
+        ("0.00000000000000266453525910037569701671600341796875000PiB", 3),
+        ("1.00000000000000088817841970012523233890533447265625PiB", (1 << 50) + 1),
+    ];
+    for (text, expected) in inputs {
+        let doc = parse_str(&format!("val = {}", text)).unwrap();
+        assert_eq!(doc["val"].as_byte_size().map(|b| b.as_bytes()), Some(expected), "{}", text);
+    }
+
+    let text = "0.00000000000000088817841970012523233890533447265626PiB";
+    match parse_str(&format!("val = {}", text)).unwrap_err() {
+        Error::InvalidValue { message, .. } => assert_eq!(message, format!("`{}` is not a whole number of bytes", text)),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn error_on_invalid_byte_size() {
+    for text in &["1.0000000001GB", "0.1KiB", "1.5B", "0.00000000000000000000000000000000000000001PB"] {
+        let input = format!("val = {}", text);
+        match parse_str(&input) {
+            Err(Error::InvalidValue { message, location }) => {
+                assert_eq!(message, format!("`{}` is not a whole number of bytes", text));
+                assert_eq!(location.span, (6, input.len()));
+            }
+            // `1.5B` is not a byte size at all
+            Err(Error::InvalidSyntax { .. }) if *text == "1.5B" => (),
+            res => panic!("Unexpected result for {}: {:?}", text, res),
+        }
+    }
+
+    for text in &["10_000_000PB", "18_446_744_073_709.551616MB", "99999999999999999999999999999999999999999kB"] {
+        let input = format!("val = {}", text);
+        match parse_str(&input).unwrap_err() {
+            Error::OutOfRange { value, type_name, location } => {
+                assert_eq!(value, *text);
+                assert_eq!(type_name, "byte size");
+                assert_eq!(location.span, (6, input.len()));
+            }
+            e => panic!("Unexpected error for {}: {:?}", text, e),
+        }
+    }
+}
+
+#[test]
+fn parse_durations() {
+    let inputs = vec![
+        ("30s", Duration::from_secs(30)),
+        ("5m", Duration::from_secs(300)),
+        ("1h30m", Duration::from_secs(5400)),
+        ("250ms", Duration::from_millis(250)),
+        ("2d", Duration::from_secs(2 * 86_400)),
+        ("1s500us7ns", Duration::new(1, 500_007)),
+        ("1_500ms", Duration::from_millis(1500)),
+        ("0s", Duration::from_secs(0)),
+    ];
+
+    for (text, expected) in inputs {
+        let doc = parse_str(&format!("val = {}", text)).unwrap();
+        assert_eq!(doc["val"], Value::Duration(expected), "{}", text);
+    }
+}
+
+#[test]
+fn error_on_invalid_duration() {
+    for input in &["val = 30m1h", "val = 1m1m", "val = 1ms1s"] {
+        match parse_str(input).unwrap_err() {
+            Error::InvalidValue { message, location } => {
+                assert_eq!(message, "the units of a duration must go from the largest to the smallest, each used once");
+                assert_eq!(location.span, (6, input.len()));
+            }
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    match parse_str("val = 300000000000000d").unwrap_err() {
+        Error::OutOfRange { type_name, .. } => assert_eq!(type_name, "duration"),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+
+    env::set_var("SCL_NOT_A_DURATION", "1h 30m");
+    match parse_str("val = ${SCL_NOT_A_DURATION as duration}").unwrap_err() {
+        Error::InvalidCast { value, .. } => assert_eq!(value, "1h 30m"),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+    match parse_str("val = ${SCL_NOT_A_DURATION as duration || 10}").unwrap_err() {
+        Error::TypeMismatch { expected, found, .. } => assert_eq!((expected, found), ("duration", "integer")),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn error_on_date_not_in_calendar() {
+    match parse_str("val = 2023-02-29").unwrap_err() {
+        Error::InvalidValue { message, location } => {
+            assert_eq!(message, "February 2023 only has 28 days");
+            assert_eq!(location.span, (6, 16));
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+
+    env::set_var("SCL_NOT_LEAP", "2023-02-29");
+    match parse_str("val = ${SCL_NOT_LEAP as date}").unwrap_err() {
+        Error::InvalidCast { value, .. } => assert_eq!(value, "2023-02-29"),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+
+    // Like in the document, years start at 1000
+    env::set_var("SCL_EARLY_DATE", "0999-01-01");
+    match parse_str("val = ${SCL_EARLY_DATE as date}").unwrap_err() {
+        Error::InvalidCast { value, .. } => assert_eq!(value, "0999-01-01"),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[cfg(feature = "preserve_order")]
+#[test]
+fn keep_keys_in_source_order() {
+    let doc = parse_str("zebra = 1\napple = { b = 2, a = 3 }\nmango = 4\nzebra = 5").unwrap();
+    assert_eq!(doc.keys().collect::<Vec<_>>(), vec!["zebra", "apple", "mango"]);
+    assert_eq!(doc["zebra"], Value::Integer(5));
+    assert_eq!(doc["apple"].as_dict().unwrap().keys().collect::<Vec<_>>(), vec!["b", "a"]);
+}
+
+#[test]
+fn parse_string_escapes() {
+    let inputs = vec![
+        (r#""The dog says \"woof\".""#, "The dog says \"woof\"."),
+        (r#""C:\\Users\\me""#, "C:\\Users\\me"),
+        (r#""a\tb\r\nc""#, "a\tb\r\nc"),
+        (r#""\u{e9}t\u{E9} \u{1F600}""#, "été 😀"),
+        (r#""\\n is not a newline""#, "\\n is not a newline"),
+        (r#""""#, ""),
+    ];
+
+    for (text, expected) in inputs {
+        let doc = parse_str(&format!("val = {}", text)).unwrap();
+        assert_eq!(doc["val"], Value::String(expected.to_string()), "{}", text);
+    }
+}
+
+#[test]
+fn error_on_invalid_escape() {
+    let inputs = vec![
+        (r#""a\qb""#, 2, 4, r#"`\q` is not a valid escape, only \", \\, \n, \t, \r and \u{XXXX} are allowed"#),
+        (r#""été\é""#, 6, 9, r#"`\é` is not a valid escape, only \", \\, \n, \t, \r and \u{XXXX} are allowed"#),
+        (r#""\u00e9""#, 1, 3, r"unicode escapes must be written as `\u{XXXX}` with 1 to 6 hexadecimal digits"),
+        (r#""\u{}""#, 1, 3, r"unicode escapes must be written as `\u{XXXX}` with 1 to 6 hexadecimal digits"),
+        (r#""\u{1234567}""#, 1, 3, r"unicode escapes must be written as `\u{XXXX}` with 1 to 6 hexadecimal digits"),
+        (r#""\u{D800}""#, 1, 9, r"`\u{D800}` is not a valid unicode character"),
+    ];
+
+    for (text, start, end, expected) in inputs {
+        match parse_str(&format!("val = {}", text)) {
+            Err(Error::InvalidValue { message, location }) => {
+                assert_eq!(message, expected, "{}", text);
+                assert_eq!(location.span, (6 + start, 6 + end), "{}", text);
+                assert_eq!(location.column, 7 + text[..start].chars().count(), "{}", text);
+            }
+            res => panic!("Unexpected result for {}: {:?}", text, res),
+        }
+    }
+
+    // The location accounts for the new lines in the string
+    let err = parse_str("a = 1\nval = \"first\nsecond \\x\"").unwrap_err();
+    let location = err.location().unwrap();
+    assert_eq!((location.line, location.column), (3, 8));
+}
+
+#[test]
+fn parse_literal_strings() {
+    let inputs = vec![
+        (r"'C:\Users\me'", r"C:\Users\me"),
+        (r#"'<\i\c*\s*> "quoted"'"#, r#"<\i\c*\s*> "quoted""#),
+        ("''", ""),
+        ("'''\nno \\n escape\nit's \"\"\" verbatim'''", "no \\n escape\nit's \"\"\" verbatim"),
+    ];
+
+    for (text, expected) in inputs {
+        let doc = parse_str(&format!("val = {}", text)).unwrap();
+        assert_eq!(doc["val"], Value::String(expected.to_string()), "{}", text);
+    }
+
+    let doc = parse_str(r"val = ${SCL_SURELY_NOT_SET || 'C:\tmp'}").unwrap();
+    assert_eq!(doc["val"], Value::String(r"C:\tmp".to_string()));
+    assert!(parse_str("val = 'two\nlines'").is_err());
+}
+
+#[test]
+fn parse_string_interpolations() {
+    env::set_var("SCL_INTERP_HOST", "db.local");
+    env::set_var("SCL_INTERP_PORT", "5432");
+    env::set_var("SCL_INTERP_LINES", "a\n  b");
+
+    let inputs = vec![
+        (r#""postgres://${SCL_INTERP_HOST}:${SCL_INTERP_PORT}/app""#, "postgres://db.local:5432/app"),
+        (r#""${ SCL_INTERP_HOST }""#, "db.local"),
+        (r#""${SCL_INTERP_UNSET || "localhost"}:${SCL_INTERP_UNSET as integer || 80}""#, "localhost:80"),
+        (r#""${SCL_INTERP_UNSET || 'C:\tmp'}\\logs""#, r"C:\tmp\logs"),
+        (r#""timeout: ${SCL_INTERP_UNSET || 1h30m}""#, "timeout: 1h30m"),
+        (r#""${SCL_INTERP_UNSET || "${SCL_INTERP_HOST}"}""#, "db.local"),
+        // `$${` is a literal `${` and a lone `$` or `{` is kept
+        (r#""$${SCL_INTERP_HOST} $$ $ {}""#, "${SCL_INTERP_HOST} $$ $ {}"),
+        (r#""$$${SCL_INTERP_HOST}""#, "$${SCL_INTERP_HOST}"),
+        // literal strings are verbatim
+        ("'${SCL_INTERP_HOST}'", "${SCL_INTERP_HOST}"),
+        ("'''${SCL_INTERP_HOST}'''", "${SCL_INTERP_HOST}"),
+        // interpolated values are not dedented or joined
+        ("\"\"\"\n    ${SCL_INTERP_LINES} \\\n    $${x}\n    \"\"\"", "a\n  b ${x}\n"),
+        ("\"\"\"\n    host = \"${SCL_INTERP_HOST}\"\n    \"\"\"", "host = \"db.local\"\n"),
+    ];
+
+    for (text, expected) in inputs {
+        let doc = parse_str(&format!("val = {}", text)).unwrap();
+        assert_eq!(doc["val"], Value::String(expected.to_string()), "{}", text);
+    }
+}
+
+#[test]
+fn error_on_invalid_interpolation() {
+    match parse_str(r#"val = "http://${SCL_INTERP_NOT_SET}/""#).unwrap_err() {
+        Error::EnvVar { name, location, .. } => {
+            assert_eq!(name, "SCL_INTERP_NOT_SET");
+            assert_eq!(location.span, (14, 35));
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+
+    match parse_str("val = \"\"\"\n    a\n  ${SCL_INTERP_UNSET || \"b\"}\n    \"\"\"").unwrap_err() {
+        Error::InvalidValue { message, location } => {
+            assert_eq!(message, "this line is less indented than the closing `\"\"\"`");
+            assert_eq!((location.line, location.column), (3, 1));
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+
+    // `${` has to be a valid environment variable, `$${` being a literal one
+    for input in &[r#"val = "cost ${""#, r#"val = "${1}""#, r#"val = "${A as string}""#, "val = \"\"\"${\"\"\""] {
+        match parse_str(input) {
+            Err(Error::InvalidSyntax { .. }) => (),
+            res => panic!("Unexpected result for {}: {:?}", input, res),
+        }
+    }
+}
+
+#[test]
+fn error_on_too_much_interpolation_nesting() {
+    let input = format!("val = {}{}", "\"${A || ".repeat(1000), "1}\"".repeat(1000));
+    match parse_str(&input).unwrap_err() {
+        Error::InvalidSyntax { message, .. } => assert!(message.contains("nested"), "{}", message),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn parse_references() {
+    let input = r#"
+    replica_host = &database.server
+    backup_port = &database.ports[1]
+    url = "postgres://${&database.server}:${ &database.ports[0] }/app"
+    summary = """
+        ${&database.server} takes ${&database.connection_max} connections
+        """
+    database = {
+        server = "192.168.1.1",
+        ports = [8001, 8002],
+        connection_max = 5000,
+        replica = &replica_host,
+    }
+    copy = &database
+    ports = [&backup_port, 8003, &database.ports[0]]
+    "#;
+    let doc = parse_str(input).unwrap();
+
+    let server = Value::String("192.168.1.1".to_string());
+    assert_eq!(doc["replica_host"], server);
+    assert_eq!(doc["backup_port"], Value::Integer(8002));
+    assert_eq!(doc["url"], Value::String("postgres://192.168.1.1:8001/app".to_string()));
+    assert_eq!(doc["summary"], Value::String("192.168.1.1 takes 5000 connections\n".to_string()));
+    assert_eq!(doc["database"].as_dict().unwrap()["replica"], server);
+    assert_eq!(doc["copy"], doc["database"]);
+    assert_eq!(doc["ports"], Value::Array(vec![Value::Integer(8002), Value::Integer(8003), Value::Integer(8001)]));
+
+    // A reference sees the last value set for a key and `$${&` is not a reference
+    let doc = parse_str("a = &b\nb = 1\nb = 2\nc = \"$${&b}\"").unwrap();
+    assert_eq!(doc["a"], Value::Integer(2));
+    assert_eq!(doc["c"], Value::String("${&b}".to_string()));
+}
+
+#[test]
+fn parse_references_across_includes() {
+    let tmp_dir = TempDir::new("tests").unwrap();
+    let included = tmp_dir.path().join("replica.scl");
+    let mut f = File::create(&included).unwrap();
+    f.write_all(b"host = &database.server\nport = &missing\n").unwrap();
+    f.sync_all().unwrap();
+
+    let input = format!(
+        r#"
+        database = {{ server = "10.0.0.1" }}
+        replica = include "{path}"
+        replica = {{ host = &host }}
+        include "{path}"
+        port = 5432
+        "#,
+        path = included.display()
+    );
+    let doc = parse_str(&input).unwrap();
+    assert_eq!(doc["host"], Value::String("10.0.0.1".to_string()));
+    assert_eq!(doc["port"], Value::Integer(5432));
+    assert_eq!(doc["replica"].as_dict().unwrap()["host"], Value::String("10.0.0.1".to_string()));
+
+    // Errors in included files point to the include
+    match parse_str(&format!("include \"{}\"", included.display())).unwrap_err() {
+        Error::Included { cause, .. } => match *cause {
+            Error::Reference { path, location, .. } => {
+                assert_eq!(path, "database.server");
+                assert_eq!(location.path, Some(included.clone()));
+            }
+            e => panic!("Unexpected error: {:?}", e),
+        },
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn error_on_reference_cycle() {
+    let inputs = vec![
+        ("a = &b\nb = &a", "is part of a cycle: `a` -> `b` -> `a`"),
+        ("a = &a", "is part of a cycle: `a` -> `a`"),
+        ("a = { b = [1, &a] }", "is part of a cycle: `a.b[1]` -> `a`"),
+        ("a = \"${&b}\"\nb = { c = \"${&a}\" }", "is part of a cycle: `a` -> `b` -> `a`"),
+    ];
+
+    for (input, expected) in inputs {
+        match parse_str(input).unwrap_err() {
+            Error::Reference { message, .. } => assert_eq!(message, expected, "{}", input),
+            e => panic!("Unexpected error for {}: {:?}", input, e),
+        }
+    }
+}
+
+#[test]
+fn error_on_too_long_reference_chain() {
+    let mut input: String = (0..200).map(|i| format!("a{} = &a{}\n", i, i + 1)).collect();
+    input.push_str("a200 = 1");
+    match parse_str(&input).unwrap_err() {
+        Error::Reference { message, .. } => assert_eq!(message, "can only go through 128 references in a row"),
+        e => panic!("Unexpected error: {:?}", e),
+    }
+    assert!(parse_str(&input[input.find("a100 =").unwrap()..]).is_ok());
+}
+
+#[test]
+fn resolve_many_references() {
+    let mut input: String = (0..5000).map(|i| format!("a{} = &b.c\nd{} = \"${{&a{}}}\"\n", i, i, i)).collect();
+    input.push_str("b = { c = 1 }");
+    let doc = parse_str(&input).unwrap();
+    assert_eq!(doc["a4999"], Value::Integer(1));
+    assert_eq!(doc["d4999"], Value::String("1".to_string()));
+}
+
+#[test]
+fn resolve_many_array_references() {
+    let items = vec!["&b"; 5000].join(", ");
+    let input = format!("b = 1\na = [{}]\nc = [\"x\", {}]", items, items);
+    let diagnostics = check_str(&input);
+    assert_eq!(diagnostics.len(), 5000);
+
+    let doc = parse_str(&format!("b = 1\na = [{}]", items)).unwrap();
+    assert_eq!(doc["a"], Value::Array(vec![Value::Integer(1); 5000]));
+}
+
+#[test]
+fn error_on_invalid_reference() {
+    match parse_str("a = { b = 1 }\nc = &a.d").unwrap_err() {
+        Error::Reference { path, message, location } => {
+            assert_eq!(path, "a.d");
+            assert_eq!(message, "doesn't point to any value");
+            assert_eq!(location.span, (18, 22));
+            assert_eq!((location.line, location.column), (2, 5));
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+
+    let inputs = vec![
+        ("a = [1]\nb = \"${&a}\"", "points to an array, which can't be interpolated in a string"),
+        ("a = [1]\nb = &a[1]", "doesn't point to any value"),
+        ("a = 1\nb = &a.c", "doesn't point to any value"),
+    ];
+    for (input, expected) in inputs {
+        match parse_str(input).unwrap_err() {
+            Error::Reference { message, .. } => assert_eq!(message, expected, "{}", input),
+            e => panic!("Unexpected error for {}: {:?}", input, e),
+        }
+    }
+
+    let inputs = vec![
+        ("a = 1\nb = ${SCL_REF_UNSET || \"${&a}\"}", "references can't be used in the default value of an environment variable"),
+        ("a = \"b\"\ninclude \"${&a}\"", "references can't be used in the path of an include"),
+    ];
+    for (input, expected) in inputs {
+        match parse_str(input).unwrap_err() {
+            Error::InvalidValue { message, .. } => assert_eq!(message, expected, "{}", input),
+            e => panic!("Unexpected error for {}: {:?}", input, e),
+        }
+    }
+
+    match parse_str("a = \"1\"\nb = [1, &a]").unwrap_err() {
+        Error::TypeMismatch { expected, found, location, .. } => {
+            assert_eq!((expected, found), ("integer", "string"));
+            assert_eq!(location.span, (16, 18));
+        }
+        e => panic!("Unexpected error: {:?}", e),
+    }
+}
+
+#[test]
+fn check_reports_every_invalid_reference() {
+    let lines: Vec<_> = check_str("a = &nope\nb = &a\nc = &c\nd = &e\ne = 1")
+        .into_iter()
+        .map(|d| d.error.location().unwrap().line)
+        .collect();
+    assert_eq!(lines, vec![1, 3]);
+}
+
+#[test]
+fn locate_errors_in_large_documents() {
+    let mut input: String = (0..20000).map(|i| format!("a{} = \"é {}\"\n", i, i)).collect();
+    input.push_str("b = \"é\" c");
+    let diagnostics = check_str(&input);
+    let location = diagnostics[0].error.location().unwrap();
+    assert_eq!((location.line, location.column), (20001, 7));
+}
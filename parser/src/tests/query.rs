@@ -0,0 +1,191 @@
+use errors::PathError;
+use parser::parse_str;
+use query::{get, get_mut, parse_path, parse_selector, remove, select, set, Segment, Selector};
+use value::{Dict, Value};
+
+fn document() -> Dict {
+    parse_str(r#"
+        servers = {
+            alpha = { ip = "10.0.0.1", ports = [80, 443] },
+        }
+        clients = { data = [["gamma", "delta"], [1, 2]] }
+    "#).unwrap()
+}
+
+#[test]
+fn parse_paths() {
+    assert_eq!(
+        parse_path("clients.data[0][1]").unwrap(),
+        vec![
+            Segment::Key("clients".to_string()),
+            Segment::Key("data".to_string()),
+            Segment::Index(0),
+            Segment::Index(1),
+        ]
+    );
+    assert_eq!(parse_path("a-b.c_d").unwrap().len(), 2);
+
+    let invalid = vec![
+        ("", "expected a key"),
+        ("a.", "expected a key"),
+        ("a..b", "`*` and `..` can only be used in selectors"),
+        ("a.*", "`*` and `..` can only be used in selectors"),
+        ("a[*]", "`*` and `..` can only be used in selectors"),
+        ("[0]", "expected a key"),
+        ("a[0", "unclosed `[`"),
+        ("a[]", "array indices must be positive integers"),
+        ("a[-1]", "array indices must be positive integers"),
+        ("a[0]b", "expected a `.` or a `[` after a key"),
+        ("a b", "expected a `.` or a `[` after a key"),
+    ];
+    for (path, message) in invalid {
+        assert_eq!(
+            parse_path(path).unwrap_err(),
+            PathError { path: path.to_string(), message: message.to_string() },
+            "{}",
+            path
+        );
+    }
+}
+
+#[test]
+fn get_by_path() {
+    let doc = document();
+    assert_eq!(get(&doc, "servers.alpha.ip"), Some(&Value::String("10.0.0.1".to_string())));
+    assert_eq!(get(&doc, "servers.alpha.ports[1]"), Some(&Value::Integer(443)));
+    assert_eq!(get(&doc, "clients.data[0][1]"), Some(&Value::String("delta".to_string())));
+    assert!(get(&doc, "clients.data[1]").unwrap().is_array());
+
+    assert_eq!(get(&doc, "servers.beta"), None);
+    assert_eq!(get(&doc, "servers.alpha.ports[2]"), None);
+    assert_eq!(get(&doc, "servers.alpha.ip.nope"), None);
+    assert_eq!(get(&doc, "servers[0]"), None);
+    assert_eq!(get(&doc, "servers..alpha"), None);
+}
+
+#[test]
+fn get_mut_by_path() {
+    let mut doc = document();
+    *get_mut(&mut doc, "servers.alpha.ports[0]").unwrap() = Value::Integer(8080);
+    assert_eq!(get(&doc, "servers.alpha.ports[0]"), Some(&Value::Integer(8080)));
+    assert!(get_mut(&mut doc, "servers.beta").is_none());
+}
+
+#[test]
+fn set_by_path() {
+    let mut doc = document();
+    assert_eq!(
+        set(&mut doc, "servers.alpha.ip", Value::String("10.0.0.2".to_string())),
+        Ok(Some(Value::String("10.0.0.1".to_string())))
+    );
+    assert_eq!(set(&mut doc, "servers.beta.ip", Value::Boolean(true)), Ok(None));
+    assert_eq!(get(&doc, "servers.beta.ip"), Some(&Value::Boolean(true)));
+    assert_eq!(set(&mut doc, "new.deeply.nested", Value::Integer(1)), Ok(None));
+    assert_eq!(get(&doc, "new.deeply.nested"), Some(&Value::Integer(1)));
+    assert_eq!(set(&mut doc, "clients.data[1][0]", Value::Integer(3)), Ok(Some(Value::Integer(1))));
+    assert_eq!(set(&mut doc, "top", Value::Integer(3)), Ok(None));
+    assert_eq!(doc["top"], Value::Integer(3));
+}
+
+#[test]
+fn error_on_set_by_invalid_path() {
+    let mut doc = document();
+    let before = doc.clone();
+    let errors = vec![
+        ("servers.alpha.ip.nope", "`servers.alpha.ip` is string and not a dict"),
+        ("servers.alpha.ports.nope", "`servers.alpha.ports` is array and not a dict"),
+        ("servers.alpha[0]", "`servers.alpha` is dict and not an array"),
+        ("servers.alpha.ports[2]", "`servers.alpha.ports` only has 2 items"),
+        ("missing.list[0]", "`missing` doesn't exist and only dicts can be created"),
+        ("servers.gamma.ports[0].x", "`servers.gamma` doesn't exist and only dicts can be created"),
+        ("servers..alpha", "`*` and `..` can only be used in selectors"),
+    ];
+
+    for (path, message) in errors {
+        let err = set(&mut doc, path, Value::Integer(1)).unwrap_err();
+        assert_eq!(err.message, message, "{}", path);
+        assert_eq!(err.path, path);
+    }
+    // Nothing was created by the failed calls
+    assert_eq!(doc, before);
+}
+
+#[test]
+fn remove_by_path() {
+    let mut doc = document();
+    assert_eq!(remove(&mut doc, "servers.alpha.ports[0]"), Some(Value::Integer(80)));
+    assert_eq!(get(&doc, "servers.alpha.ports[0]"), Some(&Value::Integer(443)));
+    assert_eq!(remove(&mut doc, "servers.alpha.ip"), Some(Value::String("10.0.0.1".to_string())));
+    assert_eq!(get(&doc, "servers.alpha.ip"), None);
+    assert_eq!(remove(&mut doc, "servers.alpha.ports[5]"), None);
+    assert_eq!(remove(&mut doc, "servers.nope.ip"), None);
+    assert!(remove(&mut doc, "clients").is_some());
+    assert!(!doc.contains_key("clients"));
+    assert_eq!(remove(&mut doc, "a[0"), None);
+}
+
+#[test]
+fn parse_selectors() {
+    assert_eq!(
+        parse_selector("..port").unwrap(),
+        vec![Selector::Descendant("port".to_string())]
+    );
+    assert_eq!(
+        parse_selector("servers.*.hosts[*][0]..ip").unwrap(),
+        vec![
+            Selector::Segment(Segment::Key("servers".to_string())),
+            Selector::AnyKey,
+            Selector::Segment(Segment::Key("hosts".to_string())),
+            Selector::AnyIndex,
+            Selector::Segment(Segment::Index(0)),
+            Selector::Descendant("ip".to_string()),
+        ]
+    );
+
+    let invalid = vec![
+        ("..*", "expected a key after `..`"),
+        ("a...b", "expected a key"),
+        ("a[**]", "array indices must be positive integers"),
+    ];
+    for (selector, message) in invalid {
+        assert_eq!(parse_selector(selector).unwrap_err().message, message, "{}", selector);
+    }
+}
+
+#[test]
+fn select_with_wildcards() {
+    let doc = parse_str(r#"
+        clients = { hosts = ["a", "b"], ips = [["c"], ["d", "e"]] }
+        port = 1
+        servers = {
+            alpha = { ip = "10.0.0.1", port = 80 },
+            beta = { backup = { port = 8443 }, ip = "10.0.0.2", port = 443 },
+        }
+    "#).unwrap();
+    let paths = |selector: &str| -> Vec<String> {
+        select(&doc, selector).unwrap().into_iter().map(|(p, _)| p).collect()
+    };
+
+    assert_eq!(paths("servers.*.ip"), vec!["servers.alpha.ip", "servers.beta.ip"]);
+    assert_eq!(paths("clients.hosts[*]"), vec!["clients.hosts[0]", "clients.hosts[1]"]);
+    assert_eq!(paths("clients.ips[*][0]"), vec!["clients.ips[0][0]", "clients.ips[1][0]"]);
+    assert_eq!(
+        paths("..port"),
+        vec!["port", "servers.alpha.port", "servers.beta.backup.port", "servers.beta.port"]
+    );
+    assert_eq!(paths("servers..port"), vec!["servers.alpha.port", "servers.beta.backup.port", "servers.beta.port"]);
+    assert_eq!(paths("*"), vec!["clients", "port", "servers"]);
+    assert_eq!(paths("servers.alpha.ip"), vec!["servers.alpha.ip"]);
+    assert!(paths("servers.*.nope").is_empty());
+    assert!(paths("port[*]").is_empty());
+
+    let found = select(&doc, "servers.*.port").unwrap();
+    assert_eq!(
+        found,
+        vec![
+            ("servers.alpha.port".to_string(), &Value::Integer(80)),
+            ("servers.beta.port".to_string(), &Value::Integer(443)),
+        ]
+    );
+    assert!(select(&doc, "servers.[0]").is_err());
+}
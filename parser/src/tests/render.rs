@@ -0,0 +1,91 @@
+use std::env;
+
+use ::parser::{check_str, parse_str};
+use render::Renderer;
+
+fn render_error(input: &str) -> String {
+    let err = parse_str(input).unwrap_err();
+    Renderer::new().render(&err, Some(input))
+}
+
+#[test]
+fn render_syntax_error() {
+    let input = "title = \"hello\"\nport =\n";
+    assert_eq!(render_error(input), "\
+error: invalid syntax
+ --> 2:7
+  |
+2 | port =
+  |       ^ expected include or string / int / float / byte size / duration / date / datetime / time / bool / array / dict / environment variable / reference
+");
+}
+
+#[test]
+fn render_several_spans() {
+    let input = "port = ${SCL_RENDER_PORT as integer || false}";
+    assert_eq!(render_error(input), "\
+error: mismatched types
+ --> 1:40
+  |
+1 | port = ${SCL_RENDER_PORT as integer || false}
+  |                             ------- expected because of this
+  |                                        ^^^^^ expected integer, found bool
+");
+}
+
+#[test]
+fn render_spans_on_different_lines() {
+    let input = "ports = [\n\t1,\n\t\"2\",\n]";
+    assert_eq!(render_error(input), "\
+error: mismatched types
+ --> 3:2
+  |
+2 | \t1,
+  | \t- expected because of this
+  |
+3 | \t\"2\",
+  | \t^^^ expected integer, found string
+");
+}
+
+#[test]
+fn render_help() {
+    env::remove_var("SCL_RENDER_MISSING");
+    let input = "password = ${SCL_RENDER_MISSING}";
+    assert_eq!(render_error(input), "\
+error: environment variable `SCL_RENDER_MISSING` is not set
+ --> 1:12
+  |
+1 | password = ${SCL_RENDER_MISSING}
+  |            ^^^^^^^^^^^^^^^^^^^^^ is not set
+  = help: set the variable or give it a default value: `${SCL_RENDER_MISSING || \"default\"}`
+");
+}
+
+#[test]
+fn render_warning_in_color() {
+    let input = "a = 1\na = 2";
+    let diagnostics = check_str(input);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(Renderer::new().colored(true).render_diagnostic(&diagnostics[0], Some(input)), "\
+\u{1b}[1;33mwarning:\u{1b}[0m\u{1b}[1m duplicate key `a`\u{1b}[0m
+ \u{1b}[1;34m-->\u{1b}[0m 2:1
+\u{1b}[1;34m  |\u{1b}[0m
+\u{1b}[1;34m1 |\u{1b}[0m a = 1
+\u{1b}[1;34m  |\u{1b}[0m \u{1b}[1;34m- first set here\u{1b}[0m
+\u{1b}[1;34m  |\u{1b}[0m
+\u{1b}[1;34m2 |\u{1b}[0m a = 2
+\u{1b}[1;34m  |\u{1b}[0m \u{1b}[1;33m^ set again here\u{1b}[0m
+  \u{1b}[1;34m=\u{1b}[0m \u{1b}[1;36mhelp: remove one of them, only the last one is kept\u{1b}[0m
+");
+}
+
+#[test]
+fn render_without_source() {
+    let err = parse_str("port =").unwrap_err();
+    assert_eq!(Renderer::new().render(&err, None), "\
+error: invalid syntax
+ --> 1:7
+  = 1:7: expected include or string / int / float / byte size / duration / date / datetime / time / bool / array / dict / environment variable / reference
+");
+}
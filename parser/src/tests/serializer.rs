@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use parser::{parse_str, MAX_NESTING};
+use serializer::{to_string, Serializer};
+use value::{ByteSize, ByteUnit, Date, DateTime, Dict, Time, Value};
+
+#[test]
+fn serialize_document() {
+    let doc = parse_str(r#"
+        at = 07:30:00
+        big = 1e300
+        db = { pool = { size = 8 }, url = "blabla" }
+        debug = true
+        empty = {}
+        id = 18_446_744_073_709_551_615
+        limit = 1.5MiB
+        matrix = [[1, 2], []]
+        port = 8080
+        ports = [80, 443]
+        ratio = 0.5
+        release = 1979-05-27
+        start = 1979-05-27T07:32:00.5-08:00
+        timeout = 1h30m
+        title = "hey"
+        users = [{ admin = true, name = "me" }, {}]
+    "#).unwrap();
+
+    let expected = r#"at = 07:30:00
+big = 1e300
+db = {
+    pool = {
+        size = 8,
+    },
+    url = "blabla",
+}
+debug = true
+empty = {}
+id = 18446744073709551615
+limit = 1.5MiB
+matrix = [
+    [1, 2],
+    [],
+]
+port = 8080
+ports = [80, 443]
+ratio = 0.5
+release = 1979-05-27
+start = 1979-05-27T07:32:00.5-08:00
+timeout = 1h30m
+title = "hey"
+users = [
+    {
+        admin = true,
+        name = "me",
+    },
+    {},
+]
+"#;
+    let text = to_string(&doc).unwrap();
+    assert_eq!(text, expected);
+    assert_eq!(parse_str(&text).unwrap(), doc);
+}
+
+#[test]
+fn serialize_with_indent() {
+    let doc = parse_str("a = { b = { c = [{ d = 1 }] } }").unwrap();
+    assert_eq!(
+        Serializer::new().indent(1).to_string(&doc).unwrap(),
+        "a = {\n b = {\n  c = [\n   {\n    d = 1,\n   },\n  ],\n },\n}\n"
+    );
+    assert_eq!(Serializer::new().indent(0).to_string(&doc).unwrap(), "a = {\nb = {\nc = [\n{\nd = 1,\n},\n],\n},\n}\n");
+}
+
+#[test]
+fn serialize_wide_arrays_one_item_per_line() {
+    let doc = parse_str("a = { hosts = [\"alpha.example.com\", \"beta.example.com\", \"gamma.example.com\", \"delta.example.com\"] }")
+        .unwrap();
+    let text = to_string(&doc).unwrap();
+    let expected = r#"a = {
+    hosts = [
+        "alpha.example.com",
+        "beta.example.com",
+        "gamma.example.com",
+        "delta.example.com",
+    ],
+}
+"#;
+    assert_eq!(text, expected);
+    assert_eq!(parse_str(&text).unwrap(), doc);
+
+    let doc = parse_str("a = [1, 2, 3]").unwrap();
+    assert_eq!(Serializer::new().max_array_width(9).to_string(&doc).unwrap(), "a = [1, 2, 3]\n");
+    assert_eq!(Serializer::new().max_array_width(8).to_string(&doc).unwrap(), "a = [\n    1,\n    2,\n    3,\n]\n");
+}
+
+#[test]
+fn serialize_values() {
+    let inputs = vec![
+        (Value::Float(1.0), "1.0"),
+        (Value::Float(-0.0), "-0.0"),
+        (Value::Float(1e-9), "1e-9"),
+        (Value::Float(f64::INFINITY), "inf"),
+        (Value::Float(f64::NEG_INFINITY), "-inf"),
+        (Value::Float(f64::NAN), "nan"),
+        (Value::Duration(Duration::new(90_061, 1_001_001)), "1d1h1m1s1ms1us1ns"),
+        (Value::Duration(Duration::from_secs(0)), "0s"),
+        (Value::ByteSize(ByteSize::new(1_000, ByteUnit::Kilobyte)), "1kB"),
+        (Value::Date(Date::new(2018, 1, 2).unwrap()), "2018-01-02"),
+        (Value::String("say \"hi\" now".to_string()), r#""say \"hi\" now""#),
+        (Value::String("C:\\Users\ttab".to_string()), r#""C:\\Users\ttab""#),
+        (Value::String("C:\\Users\\me".to_string()), r"'C:\Users\me'"),
+        (Value::String("it's \\d+".to_string()), r#""it's \\d+""#),
+        (Value::String("ends with \"\nquote\"".to_string()), r#""ends with \"\nquote\"""#),
+        (Value::String("bell\u{7}".to_string()), r#""bell\u{7}""#),
+        (Value::String("two\nlines".to_string()), r#""two\nlines""#),
+        (Value::String("two\nlines\n".to_string()), "\"\"\"\n    two\n    lines\n    \"\"\""),
+        (Value::String("  indented\n\n\ttab\n".to_string()), "\"\"\"\n      indented\n\n    \ttab\n    \"\"\""),
+        (Value::String("continued \\\n".to_string()), r#""continued \\\n""#),
+        (Value::String("\nleading".to_string()), r#""\nleading""#),
+    ];
+
+    for (value, expected) in inputs {
+        let mut doc = Dict::new();
+        doc.insert("val".to_string(), value);
+        assert_eq!(to_string(&doc).unwrap(), format!("val = {}\n", expected));
+    }
+}
+
+#[test]
+fn error_on_unrepresentable_values() {
+    let mut doc = Dict::new();
+    doc.insert("1key".to_string(), Value::Integer(1));
+    let err = to_string(&doc).unwrap_err();
+    assert_eq!(err.path, "1key");
+    assert_eq!(err.to_string(), "cannot write `1key`: `1key` is not a valid key");
+
+    let mut inner = Dict::new();
+    inner.insert("no space".to_string(), Value::Integer(1));
+    let mut doc = Dict::new();
+    doc.insert("a".to_string(), Value::Array(vec![Value::Dict(inner)]));
+    assert_eq!(to_string(&doc).unwrap_err().path, "a[0].no space");
+}
+
+#[test]
+fn error_on_values_not_read_back() {
+    let mut doc = Dict::new();
+    doc.insert("a".to_string(), Value::Array(vec![Value::Integer(1), Value::WideInteger(1 << 70), Value::String("x".to_string())]));
+    let err = to_string(&doc).unwrap_err();
+    assert_eq!(err.path, "a[2]");
+    assert_eq!(err.message, "arrays can't mix types, expected integer but found string");
+
+    let mut doc = Dict::new();
+    doc.insert("d".to_string(), Value::Date(Date { year: 999, month: 1, day: 1 }));
+    let err = to_string(&doc).unwrap_err();
+    assert_eq!(err.to_string(), "cannot write `d`: year 999 is not between 1000 and 9999");
+
+    let mut doc = Dict::new();
+    let date = Date { year: 999, month: 12, day: 31 };
+    doc.insert("d".to_string(), Value::Array(vec![Value::DateTime(DateTime { date, time: Time::new(0, 0, 0, 0).unwrap(), offset: 0 })]));
+    assert_eq!(to_string(&doc).unwrap_err().path, "d[0]");
+
+    let mut doc = Dict::new();
+    doc.insert("t".to_string(), Value::Time(Time { hour: 24, minute: 0, second: 0, nanosecond: 0 }));
+    assert_eq!(to_string(&doc).unwrap_err().message, "hour 24 is out of range (00 to 23)");
+}
+
+#[test]
+fn error_on_nesting_too_deep() {
+    let nested = |levels: usize| {
+        let value = (1..levels).fold(Value::Array(vec![]), |inner, _| Value::Array(vec![inner]));
+        let mut doc = Dict::new();
+        doc.insert("a".to_string(), value);
+        doc
+    };
+
+    let doc = nested(MAX_NESTING);
+    assert_eq!(parse_str(&to_string(&doc).unwrap()).unwrap(), doc);
+
+    let err = to_string(&nested(MAX_NESTING + 1)).unwrap_err();
+    assert_eq!(err.message, format!("arrays and dictionaries can only be nested {} levels deep", MAX_NESTING));
+}
+
+#[cfg(feature = "preserve_order")]
+#[test]
+fn serialize_in_source_order() {
+    let text = "name = \"scl\"\nversion = \"0.1.0\"\nauthors = [\"me\"]\ndependencies = {\n    pest = \"1\",\n    indexmap = \"2\",\n}\n";
+    assert_eq!(to_string(&parse_str(text).unwrap()).unwrap(), text);
+}
@@ -0,0 +1,292 @@
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use errors::TypeError;
+use parser::parse_str;
+use value::{ByteSize, ByteUnit, Date, DateTime, Dict, DictExt, Time, Value, Weekday};
+
+#[test]
+fn date_validates_calendar() {
+    assert!(Date::new(2024, 2, 29).is_ok());
+    assert!(Date::new(2000, 2, 29).is_ok());
+    assert!(Date::new(2023, 12, 31).is_ok());
+
+    let invalid = vec![
+        ((2023, 2, 29), "February 2023 only has 28 days"),
+        ((1900, 2, 29), "February 1900 only has 28 days"),
+        ((2023, 4, 31), "April 2023 only has 30 days"),
+        ((2023, 1, 0), "January 2023 only has 31 days"),
+        ((2023, 13, 1), "there is no month 13"),
+        ((10000, 1, 1), "year 10000 is not between 1000 and 9999"),
+        ((999, 1, 1), "year 999 is not between 1000 and 9999"),
+    ];
+    for ((year, month, day), message) in invalid {
+        assert_eq!(Date::new(year, month, day).unwrap_err().to_string(), message);
+    }
+}
+
+#[test]
+fn date_from_str() {
+    assert_eq!("2018-02-03".parse(), Ok(Date { year: 2018, month: 2, day: 3 }));
+    for input in &["2018-2-03", "2018-02-03T", "18-02-03", "2018/02/03", "", "2018-02-31", "0999-01-01"] {
+        assert!(input.parse::<Date>().is_err(), "{} should not parse", input);
+    }
+}
+
+#[test]
+fn date_iso_and_display() {
+    let date = Date::new(1079, 5, 7).unwrap();
+    assert_eq!(date.to_iso(), "1079-05-07");
+    assert_eq!(date.to_string(), "1079-05-07");
+    assert_eq!(date.to_iso().parse(), Ok(date));
+}
+
+#[test]
+fn date_ordering_and_hash() {
+    let mut dates = [
+        Date::new(2018, 2, 1).unwrap(),
+        Date::new(2017, 12, 31).unwrap(),
+        Date::new(2018, 1, 15).unwrap(),
+    ];
+    dates.sort();
+    assert_eq!(
+        dates.iter().map(|d| d.to_iso()).collect::<Vec<_>>(),
+        vec!["2017-12-31", "2018-01-15", "2018-02-01"]
+    );
+
+    let set: HashSet<_> = dates.iter().chain(dates.iter()).collect();
+    assert_eq!(set.len(), 3);
+}
+
+#[test]
+fn date_weekday() {
+    let inputs = vec![
+        ((1970, 1, 1), Weekday::Thursday),
+        ((2000, 2, 29), Weekday::Tuesday),
+        ((1969, 12, 31), Weekday::Wednesday),
+        ((2024, 6, 16), Weekday::Sunday),
+        ((1000, 1, 1), Weekday::Wednesday),
+    ];
+    for ((year, month, day), weekday) in inputs {
+        assert_eq!(Date::new(year, month, day).unwrap().weekday(), weekday);
+    }
+}
+
+#[test]
+fn date_arithmetic() {
+    let date = Date::new(2024, 2, 28).unwrap();
+    assert_eq!(date.add_days(1), Some(Date::new(2024, 2, 29).unwrap()));
+    assert_eq!(date.add_days(2), Some(Date::new(2024, 3, 1).unwrap()));
+    assert_eq!(date.add_days(-59), Some(Date::new(2023, 12, 31).unwrap()));
+    assert_eq!(date.add_days(366), Some(Date::new(2025, 2, 28).unwrap()));
+    assert_eq!(date.add_days(0), Some(date.clone()));
+
+    assert_eq!(Date::new(9999, 12, 31).unwrap().add_days(1), None);
+    assert_eq!(Date::new(1000, 1, 1).unwrap().add_days(-1), None);
+    assert_eq!(date.add_days(i64::MAX), None);
+
+    let other = Date::new(2023, 2, 28).unwrap();
+    assert_eq!(date.days_since(&other), 365);
+    assert_eq!(other.days_since(&date), -365);
+}
+
+#[test]
+fn time_validates_fields() {
+    assert!(Time::new(23, 59, 59, 999_999_999).is_ok());
+
+    let invalid = vec![
+        ((24, 0, 0, 0), "hour 24 is out of range (00 to 23)"),
+        ((12, 60, 0, 0), "minute 60 is out of range (00 to 59)"),
+        ((12, 0, 60, 0), "second 60 is out of range (00 to 59)"),
+        ((12, 0, 0, 1_000_000_000), "1000000000 nanoseconds is more than a second"),
+    ];
+    for ((hour, minute, second, nanosecond), message) in invalid {
+        assert_eq!(Time::new(hour, minute, second, nanosecond).unwrap_err().to_string(), message);
+    }
+}
+
+#[test]
+fn time_from_str_and_display() {
+    let time: Time = "07:32:00.25".parse().unwrap();
+    assert_eq!(time, Time { hour: 7, minute: 32, second: 0, nanosecond: 250_000_000 });
+    assert_eq!(time.to_string(), "07:32:00.25");
+    assert_eq!("00:00:01".parse::<Time>().unwrap().to_rfc3339(), "00:00:01");
+    assert!(Time::new(7, 0, 0, 0).unwrap() < Time::new(7, 0, 0, 1).unwrap());
+
+    for input in &["7:32:00", "07:32", "07:32:00.", "07:32:00Z", "25:00:00", "07:32:00.1234567890"] {
+        assert!(input.parse::<Time>().is_err(), "{} should not parse", input);
+    }
+}
+
+#[test]
+fn datetime_from_str_and_display() {
+    let datetime: DateTime = "1979-05-27 07:32:00.5-08:00".parse().unwrap();
+    assert_eq!(datetime.date, Date::new(1979, 5, 27).unwrap());
+    assert_eq!(datetime.time, Time::new(7, 32, 0, 500_000_000).unwrap());
+    assert_eq!(datetime.offset, -8 * 60);
+    assert_eq!(datetime.to_string(), "1979-05-27T07:32:00.5-08:00");
+    assert_eq!("1979-05-27t07:32:00z".parse::<DateTime>().unwrap().to_rfc3339(), "1979-05-27T07:32:00Z");
+
+    for input in &[
+        "1979-05-27",
+        "1979-05-27T07:32:00",
+        "1979-05-27T07:32:00+8:00",
+        "1979-05-27T07:32:00+24:00",
+        "1979-05-27_07:32:00Z",
+        "1979-02-30T07:32:00Z",
+    ] {
+        assert!(input.parse::<DateTime>().is_err(), "{} should not parse", input);
+    }
+    assert!(DateTime::new(Date::new(2000, 1, 1).unwrap(), Time::new(0, 0, 0, 0).unwrap(), 24 * 60).is_err());
+}
+
+#[test]
+fn datetime_unix_timestamp() {
+    let utc: DateTime = "1979-05-27T15:32:00Z".parse().unwrap();
+    let pacific: DateTime = "1979-05-27T07:32:00-08:00".parse().unwrap();
+    assert_eq!(utc.unix_timestamp(), 296_667_120);
+    assert_eq!(utc.unix_timestamp(), pacific.unix_timestamp());
+    assert_ne!(utc, pacific);
+    assert_eq!("1970-01-01T01:00:00+01:00".parse::<DateTime>().unwrap().unix_timestamp(), 0);
+}
+
+#[test]
+fn byte_size_display_keeps_unit() {
+    let inputs = vec![
+        (ByteSize::new(10_000_000, ByteUnit::Megabyte), "10MB"),
+        (ByteSize::new(1_500, ByteUnit::Kilobyte), "1.5kB"),
+        (ByteSize::new(1_250_000_001, ByteUnit::Gigabyte), "1.250000001GB"),
+        (ByteSize::new(512, ByteUnit::Kibibyte), "0.5KiB"),
+        (ByteSize::new(1, ByteUnit::Mebibyte), "0.00000095367431640625MiB"),
+        (ByteSize::new(0, ByteUnit::Pebibyte), "0PiB"),
+    ];
+    for (size, expected) in inputs {
+        assert_eq!(size.to_string(), expected);
+    }
+}
+
+#[test]
+fn byte_size_conversions() {
+    let size = ByteSize::new(2048, ByteUnit::Kibibyte);
+    assert_eq!(size.as_bytes(), 2048);
+    assert_eq!(u64::from(size), 2048);
+    assert_eq!(i64::try_from(size), Ok(2048));
+    assert!(i64::try_from(ByteSize::new(u64::MAX, ByteUnit::Petabyte)).is_err());
+    assert_eq!(ByteSize::try_from(Value::ByteSize(size)), Ok(size));
+    assert!(ByteSize::try_from(Value::Integer(2048)).is_err());
+    assert_ne!(Value::ByteSize(size), Value::Integer(2048));
+}
+
+#[test]
+fn value_accessors() {
+    let doc = parse_str(r#"
+        name = "scl"
+        port = 8080
+        ratio = 0.5
+        debug = true
+        release = 2018-02-03
+        timeout = 30s
+        limit = 1MB
+        ports = [1, 2]
+        db = { host = "localhost" }
+    "#).unwrap();
+
+    assert_eq!(doc["name"].as_str(), Some("scl"));
+    assert_eq!(doc["port"].as_i64(), Some(8080));
+    assert_eq!(doc["ratio"].as_f64(), Some(0.5));
+    assert_eq!(doc["port"].as_f64(), None);
+    assert_eq!(doc["debug"].as_bool(), Some(true));
+    assert_eq!(doc["release"].as_date(), Some(&Date::new(2018, 2, 3).unwrap()));
+    assert_eq!(doc["timeout"].as_duration(), Some(Duration::from_secs(30)));
+    assert_eq!(doc["limit"].as_byte_size().map(|b| b.as_bytes()), Some(1_000_000));
+    assert_eq!(doc["ports"].as_array().map(|a| a.len()), Some(2));
+    assert_eq!(doc["db"].as_dict().and_then(|d| d["host"].as_str()), Some("localhost"));
+    assert_eq!(doc["name"].as_i64(), None);
+    assert_eq!(doc["name"].as_dict(), None);
+
+    assert!(doc["name"].is_string());
+    assert!(doc["port"].is_integer());
+    assert!(!doc["port"].is_float());
+    assert!(doc["debug"].is_bool());
+    assert!(doc["release"].is_date());
+    assert!(!doc["release"].is_datetime());
+    assert!(doc["timeout"].is_duration());
+    assert!(doc["limit"].is_byte_size());
+    assert!(doc["ports"].is_array());
+    assert!(doc["db"].is_dict());
+}
+
+#[test]
+fn value_mut_accessors() {
+    let mut value = Value::Array(vec![Value::Integer(1)]);
+    value.as_array_mut().unwrap().push(Value::Integer(2));
+    *value.as_array_mut().unwrap()[0].as_i64_mut().unwrap() += 10;
+    assert_eq!(value, Value::Array(vec![Value::Integer(11), Value::Integer(2)]));
+    assert!(value.as_dict_mut().is_none());
+
+    let mut value = Value::String("a".to_string());
+    value.as_string_mut().unwrap().push('b');
+    assert_eq!(value.as_str(), Some("ab"));
+    assert!(value.as_bool_mut().is_none());
+    assert!(Value::WideInteger(i128::MAX).as_i64_mut().is_none());
+}
+
+#[test]
+fn integers_compare_by_value() {
+    assert_eq!(Value::integer(5), Value::Integer(5));
+    assert_eq!(Value::integer(i128::from(u64::MAX)), Value::WideInteger(i128::from(u64::MAX)));
+    assert_eq!(Value::WideInteger(5), Value::Integer(5));
+    assert_eq!(Value::Integer(5), Value::WideInteger(5));
+    assert_ne!(Value::WideInteger(5), Value::Integer(6));
+    assert_ne!(Value::WideInteger(5), Value::Float(5.0));
+    assert_eq!(
+        Value::Array(vec![Value::WideInteger(-1)]),
+        Value::Array(vec![Value::Integer(-1)])
+    );
+}
+
+#[test]
+fn value_try_from() {
+    assert_eq!(String::try_from(Value::String("a".to_string())), Ok("a".to_string()));
+    assert_eq!(i64::try_from(Value::Integer(-1)), Ok(-1));
+    assert_eq!(u64::try_from(Value::WideInteger(i128::from(u64::MAX))), Ok(u64::MAX));
+    assert_eq!(f64::try_from(Value::Float(1.5)), Ok(1.5));
+    assert_eq!(bool::try_from(Value::Boolean(false)), Ok(false));
+    assert_eq!(Dict::try_from(Value::Dict(Dict::new())), Ok(Dict::new()));
+
+    let err = i64::try_from(Value::String("1".to_string())).unwrap_err();
+    assert_eq!(err, TypeError { expected: "integer", found: "string" });
+    assert_eq!(err.to_string(), "expected a value of type integer, found string");
+    assert_eq!(
+        u64::try_from(Value::Integer(-1)).unwrap_err(),
+        TypeError { expected: "integer in the u64 range", found: "integer" }
+    );
+    assert_eq!(
+        bool::try_from(Value::Array(vec![])).unwrap_err(),
+        TypeError { expected: "bool", found: "array" }
+    );
+    assert_eq!(
+        DateTime::try_from(Value::Date(Date::new(2018, 1, 1).unwrap())).unwrap_err(),
+        TypeError { expected: "datetime", found: "date" }
+    );
+    assert!(Time::try_from(Value::Float(1.0)).is_err());
+}
+
+#[test]
+fn dict_accessors() {
+    let mut doc = parse_str("port = 8080\nname = \"scl\"\nhosts = [\"a\"]\ndb = { user = \"me\" }").unwrap();
+    assert_eq!(doc.get_i64("port"), Some(8080));
+    assert_eq!(doc.get_u64("port"), Some(8080));
+    assert_eq!(doc.get_str("name"), Some("scl"));
+    assert_eq!(doc.get_str("port"), None);
+    assert_eq!(doc.get_str("missing"), None);
+    assert_eq!(doc.get_array("hosts").map(|a| a.len()), Some(1));
+    assert_eq!(doc.get_dict("db").and_then(|d| d.get_str("user")), Some("me"));
+
+    doc.get_array_mut("hosts").unwrap().push(Value::String("b".to_string()));
+    doc.get_dict_mut("db").unwrap().insert("password".to_string(), Value::String("*".to_string()));
+    assert_eq!(doc.get_array("hosts").map(|a| a.len()), Some(2));
+    assert_eq!(doc.get_dict("db").and_then(|d| d.get_str("password")), Some("*"));
+    assert!(doc.get_dict_mut("port").is_none());
+}
@@ -1,68 +1,958 @@
+#[cfg(not(feature = "preserve_order"))]
 use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::num::TryFromIntError;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[cfg(feature = "preserve_order")]
+use indexmap::IndexMap;
+
+use errors::TypeError;
 
 /// Representation of a SCL value.
-#[derive(PartialEq, Clone, Debug)]
+///
+/// Integers are compared by their value: `Integer(5)` and `WideInteger(5)` are equal.
+#[derive(Clone, Debug)]
 pub enum Value {
     String(String),
     Integer(i64),
+    /// An integer that doesn't fit in an `i64`, like a large `u64` ID or hash.
+    /// The parser and `Value::integer` always give an `Integer` for the `i64` range:
+    /// use `as_u64`/`as_i128` to read any integer as a wider type.
+    ///
+    /// Those integers used to be rejected by the parser, so a match on `Integer` alone
+    /// misses them now.
+    WideInteger(i128),
     Float(f64),
     Boolean(bool),
     Date(Date),
+    DateTime(DateTime),
+    Time(Time),
+    Duration(Duration),
+    ByteSize(ByteSize),
     Array(Array),
     Dict(Dict),
 }
 
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Integer(..), _) | (Value::WideInteger(..), _) => self.as_i128() == other.as_i128(),
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Date(a), Value::Date(b)) => a == b,
+            (Value::DateTime(a), Value::DateTime(b)) => a == b,
+            (Value::Time(a), Value::Time(b)) => a == b,
+            (Value::Duration(a), Value::Duration(b)) => a == b,
+            (Value::ByteSize(a), Value::ByteSize(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            (Value::Dict(a), Value::Dict(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
 impl Value {
     /// Tests whether this and another value have the same type.
     pub fn same_type(&self, other: &Value) -> bool {
-        match (self, other) {
+        matches!(
+            (self, other),
             (&Value::String(..), &Value::String(..))
-            | (&Value::Integer(..), &Value::Integer(..))
-            | (&Value::Float(..), &Value::Float(..))
-            | (&Value::Boolean(..), &Value::Boolean(..))
-            | (&Value::Date(..), &Value::Date(..))
-            | (&Value::Array(..), &Value::Array(..))
-            | (&Value::Dict(..), &Value::Dict(..)) => true,
-            _ => false,
-        }
+                | (&Value::Integer(..), &Value::Integer(..))
+                | (&Value::Integer(..), &Value::WideInteger(..))
+                | (&Value::WideInteger(..), &Value::Integer(..))
+                | (&Value::WideInteger(..), &Value::WideInteger(..))
+                | (&Value::Float(..), &Value::Float(..))
+                | (&Value::Boolean(..), &Value::Boolean(..))
+                | (&Value::Date(..), &Value::Date(..))
+                | (&Value::DateTime(..), &Value::DateTime(..))
+                | (&Value::Time(..), &Value::Time(..))
+                | (&Value::Duration(..), &Value::Duration(..))
+                | (&Value::ByteSize(..), &Value::ByteSize(..))
+                | (&Value::Array(..), &Value::Array(..))
+                | (&Value::Dict(..), &Value::Dict(..))
+        )
     }
 
     /// Returns a human-readable representation of the type of this value.
     pub fn type_str(&self) -> &'static str {
         match *self {
             Value::String(..) => "string",
-            Value::Integer(..) => "integer",
+            Value::Integer(..) | Value::WideInteger(..) => "integer",
             Value::Float(..) => "float",
             Value::Boolean(..) => "bool",
             Value::Date(..) => "date",
+            Value::DateTime(..) => "datetime",
+            Value::Time(..) => "time",
+            Value::Duration(..) => "duration",
+            Value::ByteSize(..) => "byte size",
             Value::Array(..) => "array",
             Value::Dict(..) => "dict",
         }
     }
+
+    /// The integer as an `i128`, `None` if it's not an integer
+    pub fn as_i128(&self) -> Option<i128> {
+        match *self {
+            Value::Integer(i) => Some(i128::from(i)),
+            Value::WideInteger(i) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// The integer as an `i64`, `None` if it's not an integer or doesn't fit
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_i128().and_then(|i| i64::try_from(i).ok())
+    }
+
+    /// The integer as an `u64`, `None` if it's not an integer or doesn't fit
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_i128().and_then(|i| u64::try_from(i).ok())
+    }
+
+    pub fn is_string(&self) -> bool {
+        self.as_str().is_some()
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.as_i128().is_some()
+    }
+
+    pub fn is_float(&self) -> bool {
+        self.as_f64().is_some()
+    }
+
+    pub fn is_bool(&self) -> bool {
+        self.as_bool().is_some()
+    }
+
+    pub fn is_date(&self) -> bool {
+        self.as_date().is_some()
+    }
+
+    pub fn is_datetime(&self) -> bool {
+        self.as_datetime().is_some()
+    }
+
+    pub fn is_time(&self) -> bool {
+        self.as_time().is_some()
+    }
+
+    pub fn is_duration(&self) -> bool {
+        self.as_duration().is_some()
+    }
+
+    pub fn is_byte_size(&self) -> bool {
+        self.as_byte_size().is_some()
+    }
+
+    pub fn is_array(&self) -> bool {
+        self.as_array().is_some()
+    }
+
+    pub fn is_dict(&self) -> bool {
+        self.as_dict().is_some()
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Value::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The float, `None` if it's not a float: integers are not converted
+    pub fn as_f64(&self) -> Option<f64> {
+        match *self {
+            Value::Float(f) => Some(f),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Value::Boolean(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_date(&self) -> Option<&Date> {
+        match *self {
+            Value::Date(ref d) => Some(d),
+            _ => None,
+        }
+    }
+
+    pub fn as_datetime(&self) -> Option<&DateTime> {
+        match *self {
+            Value::DateTime(ref d) => Some(d),
+            _ => None,
+        }
+    }
+
+    pub fn as_time(&self) -> Option<&Time> {
+        match *self {
+            Value::Time(ref t) => Some(t),
+            _ => None,
+        }
+    }
+
+    pub fn as_duration(&self) -> Option<Duration> {
+        match *self {
+            Value::Duration(d) => Some(d),
+            _ => None,
+        }
+    }
+
+    pub fn as_byte_size(&self) -> Option<ByteSize> {
+        match *self {
+            Value::ByteSize(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Array> {
+        match *self {
+            Value::Array(ref a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_dict(&self) -> Option<&Dict> {
+        match *self {
+            Value::Dict(ref d) => Some(d),
+            _ => None,
+        }
+    }
+
+    pub fn as_string_mut(&mut self) -> Option<&mut String> {
+        match *self {
+            Value::String(ref mut s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Only integers in the `i64` range can be modified in place
+    pub fn as_i64_mut(&mut self) -> Option<&mut i64> {
+        match *self {
+            Value::Integer(ref mut i) => Some(i),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64_mut(&mut self) -> Option<&mut f64> {
+        match *self {
+            Value::Float(ref mut f) => Some(f),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool_mut(&mut self) -> Option<&mut bool> {
+        match *self {
+            Value::Boolean(ref mut b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_date_mut(&mut self) -> Option<&mut Date> {
+        match *self {
+            Value::Date(ref mut d) => Some(d),
+            _ => None,
+        }
+    }
+
+    pub fn as_datetime_mut(&mut self) -> Option<&mut DateTime> {
+        match *self {
+            Value::DateTime(ref mut d) => Some(d),
+            _ => None,
+        }
+    }
+
+    pub fn as_time_mut(&mut self) -> Option<&mut Time> {
+        match *self {
+            Value::Time(ref mut t) => Some(t),
+            _ => None,
+        }
+    }
+
+    pub fn as_duration_mut(&mut self) -> Option<&mut Duration> {
+        match *self {
+            Value::Duration(ref mut d) => Some(d),
+            _ => None,
+        }
+    }
+
+    pub fn as_byte_size_mut(&mut self) -> Option<&mut ByteSize> {
+        match *self {
+            Value::ByteSize(ref mut b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Array> {
+        match *self {
+            Value::Array(ref mut a) => Some(a),
+            _ => None,
+        }
+    }
+
+    pub fn as_dict_mut(&mut self) -> Option<&mut Dict> {
+        match *self {
+            Value::Dict(ref mut d) => Some(d),
+            _ => None,
+        }
+    }
+
+    /// Builds an integer value: an `Integer` if `i` fits in an `i64`, a `WideInteger` otherwise
+    pub fn integer(i: i128) -> Value {
+        match i64::try_from(i) {
+            Ok(i) => Value::Integer(i),
+            Err(_) => Value::WideInteger(i),
+        }
+    }
+}
+
+/// The units a byte size can be written with.
+/// `kB` and `KB` are the same unit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ByteUnit {
+    Kilobyte,
+    Megabyte,
+    Gigabyte,
+    Terabyte,
+    Petabyte,
+    Kibibyte,
+    Mebibyte,
+    Gibibyte,
+    Tebibyte,
+    Pebibyte,
+}
+
+impl ByteUnit {
+    /// How many bytes one of that unit is
+    pub fn bytes(self) -> u64 {
+        match self {
+            ByteUnit::Kilobyte => 1_000,
+            ByteUnit::Megabyte => 1_000_000,
+            ByteUnit::Gigabyte => 1_000_000_000,
+            ByteUnit::Terabyte => 1_000_000_000_000,
+            ByteUnit::Petabyte => 1_000_000_000_000_000,
+            ByteUnit::Kibibyte => 1 << 10,
+            ByteUnit::Mebibyte => 1 << 20,
+            ByteUnit::Gibibyte => 1 << 30,
+            ByteUnit::Tebibyte => 1 << 40,
+            ByteUnit::Pebibyte => 1 << 50,
+        }
+    }
+
+    /// The suffix of that unit in a SCL document
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ByteUnit::Kilobyte => "kB",
+            ByteUnit::Megabyte => "MB",
+            ByteUnit::Gigabyte => "GB",
+            ByteUnit::Terabyte => "TB",
+            ByteUnit::Petabyte => "PB",
+            ByteUnit::Kibibyte => "KiB",
+            ByteUnit::Mebibyte => "MiB",
+            ByteUnit::Gibibyte => "GiB",
+            ByteUnit::Tebibyte => "TiB",
+            ByteUnit::Pebibyte => "PiB",
+        }
+    }
+
+    /// The unit matching a suffix of a SCL document, like `MiB`
+    pub(crate) fn from_suffix(suffix: &str) -> Option<ByteUnit> {
+        let unit = match suffix {
+            "kB" | "KB" => ByteUnit::Kilobyte,
+            "MB" => ByteUnit::Megabyte,
+            "GB" => ByteUnit::Gigabyte,
+            "TB" => ByteUnit::Terabyte,
+            "PB" => ByteUnit::Petabyte,
+            "KiB" => ByteUnit::Kibibyte,
+            "MiB" => ByteUnit::Mebibyte,
+            "GiB" => ByteUnit::Gibibyte,
+            "TiB" => ByteUnit::Tebibyte,
+            "PiB" => ByteUnit::Pebibyte,
+            _ => return None,
+        };
+        Some(unit)
+    }
+}
+
+impl fmt::Display for ByteUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A byte size like `1.5MB`: the number of bytes it is and the unit it was written with.
+/// It is displayed in its unit, `1.5MB` rather than `1500000`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ByteSize {
+    pub bytes: u64,
+    pub unit: ByteUnit,
+}
+
+impl ByteSize {
+    pub fn new(bytes: u64, unit: ByteUnit) -> ByteSize {
+        ByteSize { bytes, unit }
+    }
+
+    /// The number of bytes, regardless of the unit
+    pub fn as_bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unit = self.unit.bytes();
+        write!(f, "{}", self.bytes / unit)?;
+
+        // Units are made of 2s and 5s so the decimal part always ends
+        let mut remainder = u128::from(self.bytes % unit);
+        if remainder > 0 {
+            write!(f, ".")?;
+        }
+        while remainder > 0 {
+            remainder *= 10;
+            write!(f, "{}", remainder / u128::from(unit))?;
+            remainder %= u128::from(unit);
+        }
+
+        write!(f, "{}", self.unit)
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> u64 {
+        size.bytes
+    }
+}
+
+impl TryFrom<ByteSize> for i64 {
+    type Error = TryFromIntError;
+
+    fn try_from(size: ByteSize) -> Result<i64, TryFromIntError> {
+        i64::try_from(size.bytes)
+    }
 }
 
 pub type Array = Vec<Value>;
+/// Keys are sorted, unless the `preserve_order` feature is enabled: they are then kept
+/// in the order they were written in the document.
+#[cfg(not(feature = "preserve_order"))]
 pub type Dict = BTreeMap<String, Value>;
+#[cfg(feature = "preserve_order")]
+pub type Dict = IndexMap<String, Value>;
 
-#[derive(Clone, Debug, PartialEq)]
+/// Implements `TryFrom<Value>` for the types held by a single variant
+macro_rules! try_from_value {
+    ($($ty:ty => $variant:ident, $type_str:expr;)+) => {
+        $(
+            impl TryFrom<Value> for $ty {
+                type Error = TypeError;
+
+                fn try_from(value: Value) -> Result<$ty, TypeError> {
+                    match value {
+                        Value::$variant(v) => Ok(v),
+                        v => Err(TypeError { expected: $type_str, found: v.type_str() }),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+try_from_value! {
+    String => String, "string";
+    f64 => Float, "float";
+    bool => Boolean, "bool";
+    Date => Date, "date";
+    DateTime => DateTime, "datetime";
+    Time => Time, "time";
+    Duration => Duration, "duration";
+    ByteSize => ByteSize, "byte size";
+    Array => Array, "array";
+    Dict => Dict, "dict";
+}
+
+/// Implements `TryFrom<Value>` for integer types, checking the integer fits
+macro_rules! try_from_integer {
+    ($($ty:ty => $expected:expr;)+) => {
+        $(
+            impl TryFrom<Value> for $ty {
+                type Error = TypeError;
+
+                fn try_from(value: Value) -> Result<$ty, TypeError> {
+                    match value.as_i128() {
+                        Some(i) => <$ty>::try_from(i).map_err(|_| TypeError { expected: $expected, found: "integer" }),
+                        None => Err(TypeError { expected: "integer", found: value.type_str() }),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+try_from_integer! {
+    i64 => "integer in the i64 range";
+    u64 => "integer in the u64 range";
+    i128 => "integer";
+}
+
+/// Typed accessors for the values of a `Dict`, returning `None` if the key is
+/// missing or the value doesn't have the right type.
+///
+/// ```
+/// # use scl::{parse_str, DictExt};
+/// let config = parse_str("port = 8080").unwrap();
+/// assert_eq!(config.get_i64("port"), Some(8080));
+/// assert_eq!(config.get_str("port"), None);
+/// ```
+pub trait DictExt {
+    fn get_str(&self, key: &str) -> Option<&str>;
+    fn get_i64(&self, key: &str) -> Option<i64>;
+    fn get_u64(&self, key: &str) -> Option<u64>;
+    fn get_f64(&self, key: &str) -> Option<f64>;
+    fn get_bool(&self, key: &str) -> Option<bool>;
+    fn get_date(&self, key: &str) -> Option<&Date>;
+    fn get_datetime(&self, key: &str) -> Option<&DateTime>;
+    fn get_time(&self, key: &str) -> Option<&Time>;
+    fn get_duration(&self, key: &str) -> Option<Duration>;
+    fn get_byte_size(&self, key: &str) -> Option<ByteSize>;
+    fn get_array(&self, key: &str) -> Option<&Array>;
+    fn get_dict(&self, key: &str) -> Option<&Dict>;
+    fn get_array_mut(&mut self, key: &str) -> Option<&mut Array>;
+    fn get_dict_mut(&mut self, key: &str) -> Option<&mut Dict>;
+}
+
+impl DictExt for Dict {
+    fn get_str(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(Value::as_str)
+    }
+
+    fn get_i64(&self, key: &str) -> Option<i64> {
+        self.get(key).and_then(Value::as_i64)
+    }
+
+    fn get_u64(&self, key: &str) -> Option<u64> {
+        self.get(key).and_then(Value::as_u64)
+    }
+
+    fn get_f64(&self, key: &str) -> Option<f64> {
+        self.get(key).and_then(Value::as_f64)
+    }
+
+    fn get_bool(&self, key: &str) -> Option<bool> {
+        self.get(key).and_then(Value::as_bool)
+    }
+
+    fn get_date(&self, key: &str) -> Option<&Date> {
+        self.get(key).and_then(Value::as_date)
+    }
+
+    fn get_datetime(&self, key: &str) -> Option<&DateTime> {
+        self.get(key).and_then(Value::as_datetime)
+    }
+
+    fn get_time(&self, key: &str) -> Option<&Time> {
+        self.get(key).and_then(Value::as_time)
+    }
+
+    fn get_duration(&self, key: &str) -> Option<Duration> {
+        self.get(key).and_then(Value::as_duration)
+    }
+
+    fn get_byte_size(&self, key: &str) -> Option<ByteSize> {
+        self.get(key).and_then(Value::as_byte_size)
+    }
+
+    fn get_array(&self, key: &str) -> Option<&Array> {
+        self.get(key).and_then(Value::as_array)
+    }
+
+    fn get_dict(&self, key: &str) -> Option<&Dict> {
+        self.get(key).and_then(Value::as_dict)
+    }
+
+    fn get_array_mut(&mut self, key: &str) -> Option<&mut Array> {
+        self.get_mut(key).and_then(Value::as_array_mut)
+    }
+
+    fn get_dict_mut(&mut self, key: &str) -> Option<&mut Dict> {
+        self.get_mut(key).and_then(Value::as_dict_mut)
+    }
+}
+
+/// A calendar date, from 1000-01-01 to 9999-12-31 as SCL only has 4 digit years.
+/// Dates are ordered chronologically.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Date {
-    pub day: u8,
-    pub month: u8,
+    // The fields order matters for the derived `Ord`
     pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+/// A day of the week.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl fmt::Display for Weekday {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
 }
 
+fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June", "July",
+    "August", "September", "October", "November", "December",
+];
+
 impl Date {
-    pub fn from_str(input: &str) -> Date {
-        // TODO: error handling
-        let parts: Vec<&str> = input.split('-').collect();
-        Date {
-            year: parts[0].parse().unwrap(),
-            month: parts[1].parse().unwrap(),
-            day: parts[2].parse().unwrap(),
+    /// Creates a date, checking it exists in the calendar.
+    pub fn new(year: u16, month: u8, day: u8) -> Result<Date, DateError> {
+        if !(1000..=9999).contains(&year) {
+            return Err(DateError(format!("year {} is not between 1000 and 9999", year)));
+        }
+        if !(1..=12).contains(&month) {
+            return Err(DateError(format!("there is no month {}", month)));
         }
+        let max_day = days_in_month(year, month);
+        if !(1..=max_day).contains(&day) {
+            return Err(DateError(format!(
+                "{} {} only has {} days",
+                MONTHS[month as usize - 1],
+                year,
+                max_day
+            )));
+        }
+
+        Ok(Date { year, month, day })
     }
 
+    /// Checks a date that might have been built by hand exists
+    pub(crate) fn checked(&self) -> Result<Date, DateError> {
+        Date::new(self.year, self.month, self.day)
+    }
+
+    /// The date in the ISO 8601 format: `YYYY-MM-DD`
     pub fn to_iso(&self) -> String {
-        "TODO".to_string()
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// How many days since 1970-01-01, negative for earlier dates.
+    /// From http://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    fn days_from_epoch(&self) -> i64 {
+        let month = i64::from(self.month);
+        let year = i64::from(self.year) - if month <= 2 { 1 } else { 0 };
+        let era = if year >= 0 { year } else { year - 399 } / 400;
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(self.day) - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    /// The reverse of `days_from_epoch`, `None` if the date would be out of range
+    fn from_days_from_epoch(days: i64) -> Option<Date> {
+        let days = days + 719_468;
+        let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+        let day_of_era = days - era * 146_097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        if !(1000..=9999).contains(&year) {
+            return None;
+        }
+        Some(Date { year: year as u16, month: month as u8, day: day as u8 })
+    }
+
+    /// The day of the week of that date
+    pub fn weekday(&self) -> Weekday {
+        // 1970-01-01 was a Thursday
+        match (self.days_from_epoch() + 3).rem_euclid(7) {
+            0 => Weekday::Monday,
+            1 => Weekday::Tuesday,
+            2 => Weekday::Wednesday,
+            3 => Weekday::Thursday,
+            4 => Weekday::Friday,
+            5 => Weekday::Saturday,
+            _ => Weekday::Sunday,
+        }
+    }
+
+    /// The date `days` days after this one, or before if negative.
+    /// Returns `None` if the result would be before year 1000 or after year 9999.
+    pub fn add_days(&self, days: i64) -> Option<Date> {
+        self.days_from_epoch()
+            .checked_add(days)
+            .and_then(Date::from_days_from_epoch)
+    }
+
+    /// How many days there are from `other` to this date, negative if `other` is after it
+    pub fn days_since(&self, other: &Date) -> i64 {
+        self.days_from_epoch() - other.days_from_epoch()
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_iso())
+    }
+}
+
+/// The error returned when trying to create a date or a time that doesn't exist or
+/// parsing a string that isn't in the RFC 3339 format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DateError(pub(crate) String);
+
+impl fmt::Display for DateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for Date {
+    type Err = DateError;
+
+    fn from_str(input: &str) -> Result<Date, DateError> {
+        let parts: Vec<&str> = input.split('-').collect();
+        let well_formed = parts.len() == 3
+            && parts[0].len() == 4
+            && parts[1].len() == 2
+            && parts[2].len() == 2
+            && parts.iter().all(|p| p.bytes().all(|b| b.is_ascii_digit()));
+        if !well_formed {
+            return Err(DateError(format!("`{}` is not a date, expected YYYY-MM-DD", input)));
+        }
+
+        // Those can't fail: we checked we only have a few ASCII digits
+        Date::new(parts[0].parse().unwrap(), parts[1].parse().unwrap(), parts[2].parse().unwrap())
+    }
+}
+
+/// A time of the day, without a timezone, from 00:00:00 to 23:59:59.999999999.
+/// Times are ordered chronologically.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time {
+    // The fields order matters for the derived `Ord`
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub nanosecond: u32,
+}
+
+/// Parses `count` ASCII digits at the start of `input`, returning the number and the rest
+fn parse_digits(input: &str, count: usize) -> Option<(u32, &str)> {
+    if input.len() < count || !input.as_bytes()[..count].iter().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some((input[..count].parse().unwrap(), &input[count..]))
+}
+
+impl Time {
+    /// Creates a time, checking every field is in range.
+    /// Leap seconds are not supported.
+    pub fn new(hour: u8, minute: u8, second: u8, nanosecond: u32) -> Result<Time, DateError> {
+        if hour > 23 {
+            return Err(DateError(format!("hour {} is out of range (00 to 23)", hour)));
+        }
+        if minute > 59 {
+            return Err(DateError(format!("minute {} is out of range (00 to 59)", minute)));
+        }
+        if second > 59 {
+            return Err(DateError(format!("second {} is out of range (00 to 59)", second)));
+        }
+        if nanosecond > 999_999_999 {
+            return Err(DateError(format!("{} nanoseconds is more than a second", nanosecond)));
+        }
+
+        Ok(Time { hour, minute, second, nanosecond })
+    }
+
+    /// Checks a time that might have been built by hand is in range
+    pub(crate) fn checked(&self) -> Result<Time, DateError> {
+        Time::new(self.hour, self.minute, self.second, self.nanosecond)
+    }
+
+    /// The time in the RFC 3339 format: `HH:MM:SS` followed by the fraction of
+    /// second if there is one
+    pub fn to_rfc3339(&self) -> String {
+        let mut out = format!("{:02}:{:02}:{:02}", self.hour, self.minute, self.second);
+        if self.nanosecond > 0 {
+            let fraction = format!("{:09}", self.nanosecond);
+            out.push('.');
+            out.push_str(fraction.trim_end_matches('0'));
+        }
+        out
+    }
+
+    /// How many seconds since midnight, ignoring the fraction of second
+    fn seconds_from_midnight(&self) -> i64 {
+        i64::from(self.hour) * 3600 + i64::from(self.minute) * 60 + i64::from(self.second)
+    }
+
+    /// Parses `HH:MM:SS` with an optional fraction of second from the start of the input,
+    /// returning the rest of it
+    fn parse_partial(input: &str) -> Result<(Time, &str), DateError> {
+        let invalid = || DateError(format!("`{}` is not a time, expected HH:MM:SS", input));
+        let (hour, rest) = parse_digits(input, 2).ok_or_else(invalid)?;
+        let rest = rest.strip_prefix(':').ok_or_else(invalid)?;
+        let (minute, rest) = parse_digits(rest, 2).ok_or_else(invalid)?;
+        let rest = rest.strip_prefix(':').ok_or_else(invalid)?;
+        let (second, mut rest) = parse_digits(rest, 2).ok_or_else(invalid)?;
+
+        let mut nanosecond = 0;
+        if let Some(fraction) = rest.strip_prefix('.') {
+            let length = fraction.bytes().take_while(|b| b.is_ascii_digit()).count();
+            if length == 0 {
+                return Err(invalid());
+            }
+            if length > 9 {
+                return Err(DateError("fractions of second can only have up to 9 digits".to_string()));
+            }
+            let (digits, remaining) = parse_digits(fraction, length).unwrap();
+            nanosecond = digits * 10u32.pow(9 - length as u32);
+            rest = remaining;
+        }
+
+        let time = Time::new(hour as u8, minute as u8, second as u8, nanosecond)?;
+        Ok((time, rest))
+    }
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_rfc3339())
+    }
+}
+
+impl FromStr for Time {
+    type Err = DateError;
+
+    fn from_str(input: &str) -> Result<Time, DateError> {
+        match Time::parse_partial(input)? {
+            (time, "") => Ok(time),
+            _ => Err(DateError(format!("`{}` is not a time, expected HH:MM:SS", input))),
+        }
+    }
+}
+
+/// A date and a time at a given offset from UTC, like `1979-05-27T07:32:00-08:00`.
+///
+/// Two datetimes are only equal if they have the same offset: compare their
+/// `unix_timestamp` to know if they are the same instant.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DateTime {
+    pub date: Date,
+    pub time: Time,
+    /// Minutes east of UTC, `0` for a `Z` offset
+    pub offset: i16,
+}
+
+impl DateTime {
+    /// Creates a datetime, checking the offset is less than a day
+    pub fn new(date: Date, time: Time, offset: i16) -> Result<DateTime, DateError> {
+        if offset <= -24 * 60 || offset >= 24 * 60 {
+            return Err(DateError(format!("offset of {} minutes is more than a day", offset)));
+        }
+
+        Ok(DateTime { date, time, offset })
+    }
+
+    /// Checks a datetime that might have been built by hand exists
+    pub(crate) fn checked(&self) -> Result<DateTime, DateError> {
+        DateTime::new(self.date.checked()?, self.time.checked()?, self.offset)
+    }
+
+    /// The datetime in the RFC 3339 format, like `1979-05-27T07:32:00Z`
+    pub fn to_rfc3339(&self) -> String {
+        let offset = if self.offset == 0 {
+            "Z".to_string()
+        } else {
+            let sign = if self.offset < 0 { '-' } else { '+' };
+            let minutes = self.offset.abs();
+            format!("{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+        };
+        format!("{}T{}{}", self.date.to_iso(), self.time.to_rfc3339(), offset)
+    }
+
+    /// How many seconds since 1970-01-01T00:00:00Z, ignoring the fraction of second.
+    pub fn unix_timestamp(&self) -> i64 {
+        self.date.days_from_epoch() * 86_400 + self.time.seconds_from_midnight() - i64::from(self.offset) * 60
+    }
+}
+
+impl fmt::Display for DateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_rfc3339())
+    }
+}
+
+impl FromStr for DateTime {
+    type Err = DateError;
+
+    /// Accepts `T`, `t` or a space between the date and the time, as RFC 3339 does
+    fn from_str(input: &str) -> Result<DateTime, DateError> {
+        let invalid = || DateError(format!("`{}` is not a datetime, expected YYYY-MM-DDTHH:MM:SS followed by an offset", input));
+        let separator = input.as_bytes().get(10).cloned();
+        if !input.is_char_boundary(10) || !(separator == Some(b'T') || separator == Some(b't') || separator == Some(b' ')) {
+            return Err(invalid());
+        }
+
+        let date = input[..10].parse()?;
+        let (time, offset) = Time::parse_partial(&input[11..])?;
+        let offset = match offset {
+            "Z" | "z" => 0,
+            _ => {
+                let sign = match offset.as_bytes().first() {
+                    Some(b'+') => 1,
+                    Some(b'-') => -1,
+                    _ => return Err(invalid()),
+                };
+                let (hours, rest) = parse_digits(&offset[1..], 2).ok_or_else(invalid)?;
+                let rest = rest.strip_prefix(':').ok_or_else(invalid)?;
+                let minutes = match parse_digits(rest, 2) {
+                    Some((minutes, "")) => minutes,
+                    _ => return Err(invalid()),
+                };
+                if hours > 23 || minutes > 59 {
+                    return Err(DateError(format!("offset `{}` is out of range (-23:59 to +23:59)", offset)));
+                }
+                sign * (hours * 60 + minutes) as i16
+            }
+        };
+
+        DateTime::new(date, time, offset)
     }
 }
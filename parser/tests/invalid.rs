@@ -1,26 +1,38 @@
 extern crate scl;
 
-use scl::{parse_file, Error};
+use std::path::PathBuf;
 
+use scl::{check_file, parse_file, Error, Renderer, Severity};
+
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn assert_error_msg(filename: &str, needle: &str) {
     let res = parse_file(&format!("./tests/invalid/{}.scl", filename));
     assert!(res.is_err());
     let err = res.unwrap_err();
     match err {
-        Error::InvalidSyntax(msg) => {
-            println!("{}", msg);
-            assert!(msg.contains(needle));
+        Error::InvalidSyntax { message, .. } => {
+            println!("{}", message);
+            assert!(message.contains(needle));
         }
+        _ => panic!("Expected a syntax error, got {:?}", err),
     }
 }
 
+fn assert_error_location(filename: &str, line: usize, column: usize) {
+    let path = format!("./tests/invalid/{}.scl", filename);
+    let err = parse_file(&path).unwrap_err();
+    let location = err.location().unwrap();
+    assert_eq!(location.path, Some(PathBuf::from(path)));
+    assert_eq!((location.line, location.column), (line, column));
+}
+
 // Invalid syntax errors
 
 #[test]
 fn test_eof() {
     assert_error_msg(
         "eof",
-        "expected include or string / int / float / byte size / date / bool / array / dict / environment variable"
+        "expected include or string / int / float / byte size / duration / date / datetime / time / bool / array / dict / environment variable"
     );
 }
 
@@ -28,7 +40,7 @@ fn test_eof() {
 fn test_invalid_int() {
     assert_error_msg(
         "invalid_int",
-        "expected a byte size unit (kB / MB / GB / TB / PB)",
+        "expected a byte size unit (kB / MB / GB / TB / PB / KiB / MiB / GiB / TiB / PiB)",
     );
 }
 
@@ -36,7 +48,7 @@ fn test_invalid_int() {
 fn test_env_var_default() {
     assert_error_msg(
         "env_var_default",
-        "expected a boolean (true / false), a string, a multiline string, an integer, a float, or a date"
+        "expected a boolean (true / false), a string, a multiline string, a literal string, a multiline literal string, an integer, a float, a duration, a date, a time, or a datetime"
     );
 }
 
@@ -57,7 +69,7 @@ fn test_invalid_document() {
 fn test_invalid_date() {
     assert_error_msg(
         "invalid_date",
-        "expected a byte size unit (kB / MB / GB / TB / PB)",
+        "expected a byte size unit (kB / MB / GB / TB / PB / KiB / MiB / GiB / TiB / PiB)",
     );
 }
 
@@ -65,6 +77,109 @@ fn test_invalid_date() {
 fn test_invalid_array_comment() {
     assert_error_msg(
         "invalid_array_comment",
-        "expected string / int / float / byte size / date / bool / array / dict / environment variable"
+        "expected string / int / float / byte size / duration / date / datetime / time / bool / array / dict / environment variable"
     );
 }
+
+#[test]
+fn test_syntax_error_location() {
+    assert_error_location("eof", 1, 6);
+    assert_error_location("invalid_date", 1, 12);
+    assert_error_location("invalid_array_comment", 2, 1);
+}
+
+// Other errors
+
+#[test]
+fn test_missing_file() {
+    let err = parse_file("./tests/invalid/not_here.scl").unwrap_err();
+    match err {
+        Error::Io { path, .. } => assert_eq!(path, PathBuf::from("./tests/invalid/not_here.scl")),
+        _ => panic!("Expected an io error, got {:?}", err),
+    }
+}
+
+#[test]
+fn test_missing_include() {
+    let err = parse_file("./tests/invalid/missing_include.scl").unwrap_err();
+    match err {
+        Error::Include { path, location, .. } => {
+            assert_eq!(path, PathBuf::from("./tests/invalid/not_here.scl"));
+            assert_eq!((location.line, location.column), (2, 6));
+        }
+        _ => panic!("Expected an include error, got {:?}", err),
+    }
+}
+
+#[test]
+fn test_error_in_included_file() {
+    let err = parse_file("./tests/invalid/include_chain.scl").unwrap_err();
+    match *err.root_cause() {
+        Error::InvalidSyntax { ref location, .. } => {
+            assert_eq!(location.path, Some(PathBuf::from("./tests/invalid/includes/db.scl")));
+            assert_eq!((location.line, location.column), (3, 7));
+        }
+        _ => panic!("Expected a syntax error, got {:?}", err),
+    }
+    assert_eq!(err.location(), err.root_cause().location());
+
+    let chain: Vec<_> = err.include_chain()
+        .iter()
+        .map(|l| (l.path.clone().unwrap(), l.line, l.column))
+        .collect();
+    assert_eq!(chain, vec![
+        (PathBuf::from("./tests/invalid/includes/base.scl"), 3, 12),
+        (PathBuf::from("./tests/invalid/include_chain.scl"), 2, 1),
+    ]);
+    assert!(err.to_string().ends_with(
+        "\n  included from ./tests/invalid/includes/base.scl:3:12\
+         \n  included from ./tests/invalid/include_chain.scl:2:1"
+    ));
+}
+
+#[test]
+fn test_check_reports_everything() {
+    let diagnostics = check_file("./tests/invalid/many_errors.scl");
+    for d in &diagnostics {
+        println!("{}", d);
+    }
+
+    let found: Vec<_> = diagnostics
+        .iter()
+        .map(|d| {
+            let location = d.error.location().unwrap();
+            (d.severity, location.line, location.column)
+        })
+        .collect();
+    assert_eq!(found, vec![
+        (Severity::Error, 3, 7),
+        (Severity::Error, 6, 12),
+        (Severity::Error, 12, 1),
+        (Severity::Error, 7, 16),
+        (Severity::Error, 9, 16),
+        (Severity::Error, 9, 30),
+        (Severity::Warning, 10, 1),
+        // the error from `includes/db.scl`
+        (Severity::Error, 3, 7),
+    ]);
+    assert_eq!(diagnostics[7].error.include_chain().len(), 2);
+}
+
+#[test]
+fn test_check_valid_file() {
+    assert_eq!(check_file("./tests/valid/cargo.scl"), vec![]);
+}
+
+#[test]
+fn test_render_error_in_included_file() {
+    let err = parse_file("./tests/invalid/include_chain.scl").unwrap_err();
+    assert_eq!(Renderer::new().render(&err, None), "\
+error: invalid syntax
+ --> ./tests/invalid/includes/db.scl:3:7
+  |
+3 | port =
+  |       ^ expected include or string / int / float / byte size / duration / date / datetime / time / bool / array / dict / environment variable / reference
+  = note: included from ./tests/invalid/includes/base.scl:3:12
+  = note: included from ./tests/invalid/include_chain.scl:2:1
+");
+}
@@ -0,0 +1,50 @@
+//! Property tests making sure no input can make the parser panic: every
+//! problem has to be reported as an `Error`.
+extern crate proptest;
+extern crate scl;
+
+use proptest::prelude::*;
+use scl::{check_str, parse_str};
+
+const BASIC: &str = include_str!("valid/basic.scl");
+const TOML_EXAMPLE: &str = include_str!("valid/toml_example.scl");
+
+/// Bits of SCL syntax that are put together randomly to reach the
+/// interesting parts of the parser faster than random strings
+fn fragment() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(vec![
+        "a", "key", "_k-1", " ", "\t", "\n", "\r\n", "=", ",", "#", "# comment\n",
+        "[", "]", "{", "}", "\"", "\"\"\"", "\"str\"", "\\", "\\\"", "\\u{", "\\u{D800}", "\\u{1F600}", "\\\n", "\"\"\"\n  a\n  \"\"\"", "\"${A}\"", "$${", "&", "&key.a[0]", "\"${&key}\"", "'", "'''", "'lit'", "true", "false",
+        "0", "1", "-1", "1_000", "1_0", "9223372036854775807", "9223372036854775808",
+        "18446744073709551616", "+", "0x", "0xFF_FF", "0o", "0o777", "0b", "0b1_0000", "170141183460469231731687303715884105728",
+        "99999999999999999999999", "1.5", "-0.0", "1e10", "1e-9", "E", "1.5e999", "inf", "-nan", "10MB", "1.5kB", "99999999PB", "1.5KiB", "8GiB", "99999999PiB",
+        "2018-01-01", "2018-13-45", "0000-00-00", "T", "10:00:00", "23:59:59.9999999999",
+        "Z", "+01:00", "-23:59", "30s", "1h30m", "250ms", "ns", "d", "${", "SCL_FUZZ", "SCL_FUZZ_UNSET",
+        "as", "integer", "float", "bool", "date", "datetime", "time", "duration", "||", "include", "include \"nope.scl\"",
+    ])
+}
+
+proptest! {
+    #[test]
+    fn no_panic_on_any_string(input in "\\PC*") {
+        let _ = parse_str(&input);
+        let _ = check_str(&input);
+    }
+
+    #[test]
+    fn no_panic_on_scl_fragments(fragments in prop::collection::vec(fragment(), 0..40)) {
+        let _ = parse_str(&fragments.concat());
+        let _ = check_str(&fragments.concat());
+    }
+
+    #[test]
+    fn no_panic_on_truncated_documents(cut in 0usize..1000, len in 0usize..50) {
+        for doc in &[BASIC, TOML_EXAMPLE] {
+            let start = doc.char_indices().map(|(i, _)| i).nth(cut % doc.len()).unwrap_or(0);
+            let end = doc.char_indices().map(|(i, _)| i).find(|i| *i >= start + len).unwrap_or(doc.len());
+            let input = format!("{}{}", &doc[..start], &doc[end..]);
+            let _ = parse_str(&input);
+            let _ = check_str(&input);
+        }
+    }
+}
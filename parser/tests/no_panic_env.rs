@@ -0,0 +1,22 @@
+//! Property test making sure no content of an environment variable can make the
+//! parser panic. It sets a variable, so it has its own test binary to not race
+//! with the tests reading the environment.
+extern crate proptest;
+extern crate scl;
+
+use std::env;
+
+use proptest::prelude::*;
+use scl::parse_str;
+
+proptest! {
+    #[test]
+    fn no_panic_on_env_var_content(
+        content in "\\PC*",
+        cast in prop::sample::select(vec!["", " as integer", " as float", " as bool", " as date", " as datetime", " as time", " as duration"]),
+    ) {
+        env::set_var("SCL_FUZZ_ENV", &content);
+        let _ = parse_str(&format!("val = ${{SCL_FUZZ_ENV{}}}", cast));
+        let _ = parse_str(&format!("val = ${{SCL_FUZZ_ENV{} || 1}}", cast));
+    }
+}
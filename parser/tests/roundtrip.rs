@@ -0,0 +1,95 @@
+//! Property tests making sure what the serializer writes is read back as the
+//! same document, or that it refuses to write it.
+extern crate proptest;
+extern crate scl;
+
+use std::time::Duration;
+
+use proptest::prelude::*;
+use scl::{parse_str, to_string, ByteSize, ByteUnit, Date, DateTime, Dict, Serializer, Time, Value};
+
+fn key() -> impl Strategy<Value = String> {
+    "[a-zA-Z_][a-zA-Z0-9_-]{0,8}"
+}
+
+fn integer() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        any::<i64>().prop_map(Value::Integer),
+        any::<i128>().prop_map(Value::integer),
+    ]
+}
+
+/// Dates built by hand, which might not exist
+fn date() -> impl Strategy<Value = Date> {
+    prop_oneof![
+        4 => (1000u16..10_000, 1u8..13, 1u8..32),
+        1 => (any::<u16>(), any::<u8>(), any::<u8>()),
+    ]
+    .prop_map(|(year, month, day)| Date { year, month, day })
+}
+
+/// Times built by hand, which might be out of range
+fn time() -> impl Strategy<Value = Time> {
+    prop_oneof![
+        4 => (0u8..24, 0u8..60, 0u8..60, prop_oneof![Just(0u32), 0u32..1_000_000_000]),
+        1 => (any::<u8>(), any::<u8>(), any::<u8>(), any::<u32>()),
+    ]
+    .prop_map(|(hour, minute, second, nanosecond)| Time { hour, minute, second, nanosecond })
+}
+
+fn byte_unit() -> impl Strategy<Value = ByteUnit> {
+    prop::sample::select(vec![
+        ByteUnit::Kilobyte, ByteUnit::Megabyte, ByteUnit::Gigabyte, ByteUnit::Terabyte, ByteUnit::Petabyte,
+        ByteUnit::Kibibyte, ByteUnit::Mebibyte, ByteUnit::Gibibyte, ByteUnit::Tebibyte, ByteUnit::Pebibyte,
+    ])
+}
+
+/// Values that are not arrays or dicts, all of the same type for a given `kind`
+fn scalar(kind: u8) -> BoxedStrategy<Value> {
+    match kind {
+        0 => "[a-zA-Z0-9 \n\r\t\u{0}\u{7f}\"\\\\#{}\\[\\]$=,.'é😀]{0,12}".prop_map(Value::String).boxed(),
+        1 => integer().boxed(),
+        // NaN is never equal to itself
+        2 => any::<f64>().prop_filter("NaN", |f| !f.is_nan()).prop_map(Value::Float).boxed(),
+        3 => any::<bool>().prop_map(Value::Boolean).boxed(),
+        4 => date().prop_map(Value::Date).boxed(),
+        5 => (date(), time(), prop_oneof![4 => -1439i16..1440, 1 => any::<i16>()])
+            .prop_map(|(date, time, offset)| Value::DateTime(DateTime { date, time, offset }))
+            .boxed(),
+        6 => time().prop_map(Value::Time).boxed(),
+        7 => (any::<u64>(), 0u32..1_000_000_000).prop_map(|(s, n)| Value::Duration(Duration::new(s, n))).boxed(),
+        _ => (any::<u64>(), byte_unit()).prop_map(|(b, u)| Value::ByteSize(ByteSize::new(b, u))).boxed(),
+    }
+}
+
+fn value() -> impl Strategy<Value = Value> {
+    let leaf = (0u8..9).prop_flat_map(scalar);
+    leaf.prop_recursive(4, 32, 6, |inner| {
+        prop_oneof![
+            (0u8..9).prop_flat_map(|kind| prop::collection::vec(scalar(kind), 0..5).prop_map(Value::Array)),
+            prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Array),
+            prop::collection::btree_map(key(), inner, 0..4).prop_map(|d| Value::Dict(d.into_iter().collect())),
+        ]
+    })
+}
+
+fn document() -> impl Strategy<Value = Dict> {
+    prop::collection::btree_map(key(), value(), 0..6).prop_map(|d| d.into_iter().collect())
+}
+
+proptest! {
+    #[test]
+    fn parse_reads_back_what_is_written(doc in document(), indent in 0usize..5, width in 0usize..40) {
+        if let Ok(text) = Serializer::new().indent(indent).max_array_width(width).to_string(&doc) {
+            prop_assert_eq!(parse_str(&text), Ok(doc), "{}", text);
+        }
+    }
+
+    #[test]
+    fn serializes_parsed_documents(doc in document()) {
+        if let Ok(text) = to_string(&doc) {
+            let parsed = parse_str(&text).unwrap();
+            prop_assert_eq!(to_string(&parsed), Ok(text));
+        }
+    }
+}
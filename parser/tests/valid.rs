@@ -4,7 +4,7 @@ extern crate pretty_assertions;
 
 use std::env;
 
-use scl::{parse_file, Date, Dict, Value as V};
+use scl::{parse_file, ByteSize, ByteUnit, Date, Dict, Value as V};
 
 macro_rules! btreemap {
     // trailing comma case
@@ -12,7 +12,7 @@ macro_rules! btreemap {
 
     ( $($key:expr => $value:expr),* ) => {
         {
-            let mut _map = ::std::collections::BTreeMap::new();
+            let mut _map = Dict::new();
             $(
                 let _ = _map.insert($key.to_string(), $value);
             )*
@@ -21,6 +21,7 @@ macro_rules! btreemap {
     };
 }
 
+#[allow(clippy::needless_borrows_for_generic_args)]
 fn assert_valid(filename: &str, expected: Dict) {
     let res = parse_file(&format!("./tests/valid/{}.scl", filename));
     if let Err(e) = res.clone() {
@@ -39,7 +40,7 @@ fn test_basic() {
             "title" => V::String("hey".to_string()),
             "ho" => V::Array(vec![V::Integer(1), V::Integer(2), V::Integer(3)]),
             "hey" => V::Array(vec![V::Integer(1), V::Integer(2)]),
-            "max_upload_size" => V::Integer(10000000),
+            "max_upload_size" => V::ByteSize(ByteSize::new(10_000_000, ByteUnit::Megabyte)),
             "db" => V::Dict(btreemap!(
                 "url" => V::String("blabla".to_string()),
                 "password" => V::String("****".to_string()),
@@ -59,7 +60,7 @@ fn test_with_includes() {
     assert_valid("with_includes", btreemap!(
         "debug" => V::Boolean(false),
         "hostname" => V::String("something else".to_string()),
-        "max_upload_size" => V::Integer(1000000000),
+        "max_upload_size" => V::ByteSize(ByteSize::new(1_000_000_000, ByteUnit::Gigabyte)),
         "logging" => V::Dict(btreemap!(
             "enabled" => V::Boolean(true),
             "dir" => V::String("/var/logs".to_string()),
@@ -94,6 +95,7 @@ fn test_cargo() {
 }
 
 #[test]
+#[allow(clippy::zero_prefixed_literal)]
 fn test_toml_example_converted() {
     assert_valid(
         "toml_example",